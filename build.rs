@@ -0,0 +1,49 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Compile every puzzle under `archive/` into the binary so it runs from any
+// directory. The generated table maps `folder/NNN.json` to the file contents.
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let archive = manifest_dir.join("archive");
+    println!("cargo:rerun-if-changed=archive");
+
+    let mut files = Vec::new();
+    collect(&archive, &archive, &mut files);
+    files.sort();
+
+    let mut table = String::from("pub static EMBEDDED_ARCHIVE: &[(&str, &str)] = &[\n");
+    for (relative, path) in &files {
+        table.push_str(&format!(
+            "    ({:?}, include_str!({:?})),\n",
+            relative,
+            path.display().to_string()
+        ));
+    }
+    table.push_str("];\n");
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("embedded_archive.rs");
+    fs::write(out, table).unwrap();
+}
+
+fn collect(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            println!("cargo:rerun-if-changed={}", path.display());
+            collect(root, &path, files);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            let relative = path.strip_prefix(root).unwrap();
+            // always `/`, whatever the host uses
+            let relative: Vec<_> = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            files.push((relative.join("/"), path));
+        }
+    }
+}
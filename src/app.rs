@@ -1,20 +1,70 @@
-use crate::game::{Direction, Game, PlayerOperation};
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::save::{self, SavedGame};
+use crate::stats::Stats;
+use akartui_rs::archive::{ArchiveIndex, ArchiveSource, Preview, Query};
+use akartui_rs::error::PuzzleError;
+use akartui_rs::game::{
+    CellType, Direction, Game, PlayerObject, PlayerOperation, Puzzle, PuzzleId,
+};
+use akartui_rs::generator::{self, GeneratorOptions, Progress};
+use akartui_rs::notation;
+use akartui_rs::pzpr;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use rand::Rng;
 use ratatui::widgets::ListState;
-use std::time::{Duration, Instant};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, PartialEq)]
 pub enum CurrentScreen {
     Menu,
     Game,
+    Paused,
     Archive,
+    Generate,
+    Import,
+    Statistics,
     Settings,
     Help,
     Exiting,
     Win,
 }
 
+/// A message drawn over the screen until any key is pressed.
+pub struct Popup {
+    pub title: String,
+    pub text: String,
+    pub is_error: bool,
+}
+
+impl Popup {
+    pub fn info(title: impl Into<String>, text: impl Into<String>) -> Popup {
+        Popup {
+            title: title.into(),
+            text: text.into(),
+            is_error: false,
+        }
+    }
+
+    pub fn error(title: impl Into<String>, text: impl Into<String>) -> Popup {
+        Popup {
+            is_error: true,
+            ..Popup::info(title, text)
+        }
+    }
+}
+
+/// A puzzle being built on a background thread, so the screen keeps drawing.
+pub struct Generation {
+    pub progress: Arc<Progress>,
+    pub started: Instant,
+    handle: JoinHandle<Option<Puzzle>>,
+}
+
 //#[derive(Debug)]
 pub struct App {
     pub current_screen: CurrentScreen,
@@ -23,14 +73,45 @@ pub struct App {
     pub game: Option<Game>,
     pub timer_start: Option<Instant>,
     pub timer_elapsed: Duration,
+    pub archive: ArchiveIndex,
     pub archive_filter: Option<String>,
+    pub archive_query: Query,
+    /// Why the last search could not be parsed.
+    pub archive_error: Option<String>,
+    // previews already loaded, by puzzle ID
+    previews: HashMap<PuzzleId, Result<Preview, String>>,
     pub archive_input_mode: bool,
     pub archive_input: String,
+    pub generator_options: GeneratorOptions,
+    pub generator_field: usize,
+    pub generation: Option<Generation>,
+    /// URL being typed or pasted on the Import screen.
+    pub import_input: String,
+    pub import_error: Option<String>,
+    pub notice: Option<String>,
+    pub popup: Option<Popup>,
+    /// Puzzles with an unfinished game saved.
+    pub saved_games: BTreeSet<PuzzleId>,
+    pub stats: Stats,
+    // undo and hint counts when the current session began
+    session_start: (u32, u32),
     exit: bool,
 }
 
 impl App {
-    pub fn new() -> App {
+    pub fn new(source: ArchiveSource, library: PathBuf) -> App {
+        let archive = ArchiveIndex::load(source, library);
+        let (stats, notice) = match Stats::load() {
+            Ok(stats) => (stats, None),
+            Err(e) => (Stats::default(), Some(e)),
+        };
+        let popup = (!archive.errors.is_empty()).then(|| {
+            let errors: Vec<String> = archive.errors.iter().map(|e| e.to_string()).collect();
+            Popup::error(
+                format!("Skipped {} puzzle file(s)", errors.len()),
+                errors.join("\n"),
+            )
+        });
         App {
             current_screen: CurrentScreen::Menu,
             menu_list: ListState::default(),
@@ -38,9 +119,23 @@ impl App {
             game: None,
             timer_start: None,
             timer_elapsed: Duration::ZERO,
+            archive,
             archive_filter: None,
+            archive_query: Query::default(),
+            archive_error: None,
+            previews: HashMap::new(),
             archive_input_mode: false,
             archive_input: String::new(),
+            generator_options: GeneratorOptions::default(),
+            generator_field: 0,
+            generation: None,
+            import_input: String::new(),
+            import_error: None,
+            notice,
+            popup,
+            saved_games: save::saved_ids(),
+            stats,
+            session_start: (0, 0),
             exit: false,
         }
     }
@@ -49,25 +144,56 @@ impl App {
         self.exit
     }
 
-    pub fn timer_string(&self) -> String {
-        let elapsed = if self.current_screen == CurrentScreen::Game {
-            if let Some(start) = self.timer_start {
-                start.elapsed()
-            } else {
-                self.timer_elapsed
+    // Time spent on the current game, including earlier sessions.
+    fn elapsed(&self) -> Duration {
+        match self.timer_start {
+            Some(start) if self.current_screen == CurrentScreen::Game => {
+                self.timer_elapsed + start.elapsed()
             }
-        } else {
-            self.timer_elapsed
-        };
-        let secs = elapsed.as_secs();
+            _ => self.timer_elapsed,
+        }
+    }
+
+    pub fn timer_string(&self) -> String {
+        let secs = self.elapsed().as_secs();
         format!("{:02}:{:02}", secs / 60, secs % 60)
     }
 
+    /// Stop the clock and hide the board; does nothing outside a game.
+    pub fn pause(&mut self) {
+        if self.current_screen != CurrentScreen::Game {
+            return;
+        }
+        if let Some(start) = self.timer_start.take() {
+            self.timer_elapsed += start.elapsed();
+        }
+        if let Some(game) = &mut self.game {
+            game.pause();
+        }
+        self.current_screen = CurrentScreen::Paused;
+    }
+
+    fn resume(&mut self) {
+        if let Some(game) = &mut self.game {
+            game.resume();
+        }
+        self.timer_start = Some(Instant::now());
+        self.current_screen = CurrentScreen::Game;
+    }
+
     pub fn handle_event(&mut self, key: KeyEvent) {
+        self.notice = None;
+        if self.popup.take().is_some() {
+            return;
+        }
         match self.current_screen {
             CurrentScreen::Menu => self.handle_menu_events(key),
             CurrentScreen::Game => self.handle_game_events(key),
+            CurrentScreen::Paused => self.handle_paused_events(key),
             CurrentScreen::Archive => self.handle_archive_events(key),
+            CurrentScreen::Generate => self.handle_generate_events(key),
+            CurrentScreen::Import => self.handle_import_events(key),
+            CurrentScreen::Statistics => self.handle_statistics_events(key),
             CurrentScreen::Settings => self.handle_settings_events(key),
             CurrentScreen::Help => self.handle_help_events(key),
             CurrentScreen::Exiting => self.handle_exiting_events(key),
@@ -78,13 +204,12 @@ impl App {
     fn handle_menu_events(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Char('q') => self.exit = true,
-            KeyCode::Char('g') => {
-                let puzzle_id = rand::rng().random_range(1..=750);
-                if let Err(e) = self.start_game(puzzle_id) {
-                    eprintln!("Failed to start game: {}", e);
-                }
-            }
+            KeyCode::Char('g') => self.start_random_game(),
+            KeyCode::Char('c') => self.continue_game(),
+            KeyCode::Char('n') => self.current_screen = CurrentScreen::Generate,
             KeyCode::Char('a') => self.current_screen = CurrentScreen::Archive,
+            KeyCode::Char('i') => self.current_screen = CurrentScreen::Import,
+            KeyCode::Char('t') => self.current_screen = CurrentScreen::Statistics,
             KeyCode::Char('s') => self.current_screen = CurrentScreen::Settings,
             KeyCode::Char('h') => self.current_screen = CurrentScreen::Help,
             KeyCode::Char('e') => self.current_screen = CurrentScreen::Exiting,
@@ -97,17 +222,15 @@ impl App {
             KeyCode::Enter => {
                 if let Some(selected) = self.menu_list.selected() {
                     match selected {
-                        0 => {
-                            // New Game
-                            let puzzle_id = rand::rng().random_range(1..=750);
-                            if let Err(e) = self.start_game(puzzle_id) {
-                                eprintln!("Failed to start game: {}", e);
-                            }
-                        }
-                        1 => self.current_screen = CurrentScreen::Archive,
-                        2 => self.current_screen = CurrentScreen::Settings,
-                        3 => self.current_screen = CurrentScreen::Help,
-                        4 => self.current_screen = CurrentScreen::Exiting,
+                        0 => self.start_random_game(), // New Game
+                        1 => self.continue_game(),
+                        2 => self.current_screen = CurrentScreen::Generate,
+                        3 => self.current_screen = CurrentScreen::Archive,
+                        4 => self.current_screen = CurrentScreen::Import,
+                        5 => self.current_screen = CurrentScreen::Statistics,
+                        6 => self.current_screen = CurrentScreen::Settings,
+                        7 => self.current_screen = CurrentScreen::Help,
+                        8 => self.current_screen = CurrentScreen::Exiting,
                         _ => self.current_screen = CurrentScreen::Menu,
                     }
                 }
@@ -116,44 +239,313 @@ impl App {
         }
     }
 
-    pub fn start_game(&mut self, puzzle_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+    fn start_random_game(&mut self) {
+        let Some(puzzle_id) = self.archive.random_id() else {
+            self.notice = Some("The archive has no puzzles".to_string());
+            return;
+        };
+        if let Err(e) = self.start_game(&puzzle_id) {
+            self.show_error("Cannot start game", e);
+        }
+    }
+
+    pub fn start_game(&mut self, puzzle_id: &PuzzleId) -> Result<(), PuzzleError> {
+        let mut game = Game::new();
+        game.load_puzzle(self.archive.puzzle(puzzle_id)?)?;
+        self.stats
+            .start_attempt(puzzle_id, game.board.len(), game.board[0].len());
+        self.play(game, Duration::ZERO);
+        Ok(())
+    }
+
+    /// Pick up the saved game for `puzzle_id` where it was left.
+    pub fn resume_game(&mut self, puzzle_id: &PuzzleId) -> Result<(), String> {
+        let mut game = Game::new();
+        self.archive
+            .puzzle(puzzle_id)
+            .and_then(|puzzle| game.load_puzzle(puzzle))
+            .map_err(|e| e.to_string())?;
+        let Some(saved) = save::load(puzzle_id) else {
+            return Err(format!("no saved game for puzzle {}", puzzle_id));
+        };
+        let elapsed = saved.elapsed();
+        if !saved.restore(&mut game) {
+            return Err(format!(
+                "saved game for puzzle {} does not fit the board",
+                puzzle_id
+            ));
+        }
+        self.play(game, elapsed);
+        Ok(())
+    }
+
+    // Resume whichever game was saved last.
+    fn continue_game(&mut self) {
+        if let Some(saved) = save::latest() {
+            if let Err(e) = self.resume_game(&saved.puzzle_id) {
+                self.show_error("Cannot resume game", e);
+            }
+        }
+    }
+
+    // Store the current game's progress so it can be resumed later.
+    fn save_progress(&mut self) {
+        let Some(saved) = self
+            .game
+            .as_ref()
+            .and_then(|game| SavedGame::capture(game, self.elapsed()))
+        else {
+            return;
+        };
+        match save::write(&saved) {
+            Ok(()) => {
+                self.saved_games.insert(saved.puzzle_id);
+            }
+            Err(e) => self.notice = Some(format!("Failed to save game: {}", e)),
+        }
+    }
+
+    fn show_error(&mut self, title: &str, error: impl Display) {
+        self.popup = Some(Popup::error(title, error.to_string()));
+    }
+
+    /// Start generating a puzzle; `tick` begins the game once it is ready.
+    pub fn start_generated_game(&mut self) {
+        let seed = rand::rng().random();
+        let options = self.generator_options;
+        let progress = Arc::new(Progress::default());
+        let shared = Arc::clone(&progress);
+        let handle =
+            thread::spawn(move || generator::generate_with_progress(options, seed, &shared));
+        self.generation = Some(Generation {
+            progress,
+            started: Instant::now(),
+            handle,
+        });
+    }
+
+    // The thread is left to notice the flag and finish on its own.
+    fn cancel_generation(&mut self) {
+        if let Some(generation) = self.generation.take() {
+            generation.progress.cancel();
+        }
+    }
+
+    /// Called on every turn of the event loop, input or not.
+    pub fn tick(&mut self) {
+        let finished = self
+            .generation
+            .as_ref()
+            .is_some_and(|generation| generation.handle.is_finished());
+        if !finished {
+            return;
+        }
+        let Some(generation) = self.generation.take() else {
+            return;
+        };
+        match generation.handle.join() {
+            Ok(Some(puzzle)) => {
+                let mut game = Game::new();
+                match game.load_puzzle(puzzle) {
+                    Ok(()) => self.play(game, Duration::ZERO),
+                    Err(e) => self.show_error("Cannot start game", e),
+                }
+            }
+            Ok(None) => {}
+            Err(_) => self.show_error("Cannot generate puzzle", "the generator crashed"),
+        }
+    }
+
+    /// Play a puzzle that came from outside the archive, such as a URL or a
+    /// text file, starting with `bulbs` already placed.
+    pub fn start_imported_game(
+        &mut self,
+        puzzle: Puzzle,
+        bulbs: &[(usize, usize)],
+    ) -> Result<(), PuzzleError> {
         let mut game = Game::new();
-        game.init_game(puzzle_id)?;
+        game.load_puzzle(puzzle)?;
+        for &(row, col) in bulbs {
+            game.player_objects[row][col] = PlayerObject::Lightbulb;
+        }
+        game.update();
+        self.play(game, Duration::ZERO);
+        Ok(())
+    }
+
+    fn play(&mut self, mut game: Game, elapsed: Duration) {
         game.start();
+        self.session_start = (game.undos, game.assists.hints);
         self.game = Some(game);
         self.current_screen = CurrentScreen::Game;
         self.timer_start = Some(Instant::now());
-        self.timer_elapsed = Duration::ZERO;
-        Ok(())
+        self.timer_elapsed = elapsed;
+        self.notice = None;
+    }
+
+    // Keep a generated or imported puzzle in the library, named after where it
+    // came from, and go on playing it as that library puzzle.
+    fn save_puzzle(&mut self) {
+        let Some(puzzle) = self.game.as_mut().and_then(|game| game.puzzle.as_mut()) else {
+            return;
+        };
+        if puzzle.id.is_tracked() {
+            self.notice = Some("Only generated or imported puzzles need saving".to_string());
+            return;
+        }
+        let origin: String = puzzle
+            .metadata
+            .source
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_lowercase(),
+                _ => '-',
+            })
+            .collect();
+        let origin = match origin.trim_matches('-') {
+            "" => "imported",
+            origin => origin,
+        };
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let (rows, cols) = (puzzle.metadata.size.rows, puzzle.metadata.size.cols);
+        let name = format!("{}-{}x{}-{}", origin, rows, cols, stamp);
+        let library = self.archive.library.clone();
+        let saved = puzzle
+            .to_json()
+            .map_err(std::io::Error::from)
+            .and_then(|json| {
+                fs::create_dir_all(&library)?;
+                fs::write(library.join(format!("{}.json", name)), json)
+            });
+        self.notice = Some(match saved {
+            Ok(()) => {
+                puzzle.id = PuzzleId::Library(name.clone());
+                self.stats.start_attempt(&puzzle.id, rows, cols);
+                self.archive = ArchiveIndex::load(self.archive.source.clone(), library);
+                format!("Saved puzzle to the library as {}", name)
+            }
+            Err(e) => format!("Failed to save puzzle: {}", e),
+        });
+    }
+
+    // Add the session that just ended to the stats and write them out.
+    fn record_session(&mut self, solved: bool) {
+        let Some(game) = &self.game else {
+            return;
+        };
+        let Some(puzzle_id) = game.puzzle.as_ref().map(|puzzle| &puzzle.id) else {
+            return;
+        };
+        if !puzzle_id.is_tracked() {
+            return;
+        }
+        let (undos, hints) = self.session_start;
+        self.stats.end_session(
+            puzzle_id,
+            solved,
+            game.assists.is_clean(),
+            self.elapsed(),
+            game.undos - undos,
+            game.assists.hints - hints,
+        );
+        if let Err(e) = self.stats.save() {
+            self.notice = Some(format!("Failed to save stats: {}", e));
+        }
     }
 
     fn check_gameover(&mut self) {
-        if let Some(game) = &self.game {
-            if game.state == crate::game::GameState::GameOver {
-                self.current_screen = CurrentScreen::Win;
-                // 記錄最終時間
-                if let Some(start) = self.timer_start.take() {
-                    self.timer_elapsed = start.elapsed();
-                }
+        let Some(game) = &self.game else {
+            return;
+        };
+        if game.state == akartui_rs::game::GameState::GameOver && self.timer_start.is_some() {
+            // 記錄最終時間
+            self.record_session(true);
+            if let Some(start) = self.timer_start.take() {
+                self.timer_elapsed += start.elapsed();
+            }
+            if let Some(puzzle) = self.game.as_ref().and_then(|game| game.puzzle.as_ref()) {
+                save::remove(&puzzle.id);
+                self.saved_games.remove(&puzzle.id);
             }
+            self.current_screen = CurrentScreen::Win;
+        }
+    }
+
+    // Save and go back to the menu, leaving the game to be continued later.
+    fn leave_game(&mut self) {
+        self.save_progress();
+        self.record_session(false);
+        if let Some(game) = &mut self.game {
+            game.quit();
         }
+        self.current_screen = CurrentScreen::Menu;
     }
 
     // Add other event handlers as needed
     fn handle_game_events(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('q') => {
-                if let Some(game) = &mut self.game {
-                    game.quit();
-                }
-                self.current_screen = CurrentScreen::Menu;
-            }
+            KeyCode::Char('q') => self.leave_game(),
+            KeyCode::Char('p') => self.pause(),
             KeyCode::Char('u') => {
                 //undo
                 if let Some(game) = &mut self.game {
                     game.undo();
                 }
             }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(game) = &mut self.game {
+                    game.redo();
+                }
+            }
+            KeyCode::Home => {
+                if let Some(game) = &mut self.game {
+                    game.undo_all();
+                }
+            }
+            KeyCode::End => {
+                if let Some(game) = &mut self.game {
+                    game.redo_all();
+                }
+            }
+            KeyCode::Char('h') => {
+                if let Some(game) = &mut self.game {
+                    game.request_hint();
+                }
+            }
+            KeyCode::Char('c') => {
+                if let Some(game) = &mut self.game {
+                    game.check_progress();
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(game) = &mut self.game {
+                    game.reveal_cell();
+                    game.update();
+                }
+            }
+            KeyCode::Char('s') => {
+                if let Some(game) = &mut self.game {
+                    game.toggle_solution();
+                }
+            }
+            KeyCode::Char('w') => self.save_puzzle(),
+            KeyCode::Char('l') => {
+                if let Some(game) = &self.game {
+                    if let Some(puzzle) = &game.puzzle {
+                        self.popup = Some(share_popup(&puzzle.id, &game.board));
+                    }
+                }
+            }
+            KeyCode::Char('t') => {
+                if let Some(game) = &self.game {
+                    if let Some(puzzle) = &game.puzzle {
+                        self.popup =
+                            Some(text_popup(&puzzle.id, &game.board, &game.player_objects));
+                    }
+                }
+            }
             KeyCode::Up => {
                 if let Some(game) = &mut self.game {
                     game.player_move_cursor(Direction::Up);
@@ -203,12 +595,17 @@ impl App {
                     self.archive_input.clear();
                 }
                 KeyCode::Enter => {
-                    if self.archive_input.starts_with('/') {
-                        let filter = self.archive_input[1..].to_string();
-                        self.archive_filter = Some(filter);
-                    } else {
-                        self.archive_filter = None;
+                    let filter = self.archive_input.strip_prefix('/').unwrap_or("");
+                    match Query::parse(filter) {
+                        Ok(query) => {
+                            self.archive_filter =
+                                Some(filter.to_string()).filter(|filter| !filter.is_empty());
+                            self.archive_query = query;
+                            self.archive_error = None;
+                        }
+                        Err(e) => self.archive_error = Some(e),
                     }
+                    self.archive_list.select(Some(0));
                     self.archive_input_mode = false;
                     self.archive_input.clear();
                 }
@@ -239,12 +636,40 @@ impl App {
                 self.archive_list.select_next();
             }
             KeyCode::Enter => {
-                if let Some(selected) = self.archive_list.selected() {
-                    let filtered = self.filtered_archive_items();
-                    if let Some((puzzle_id, _)) = filtered.get(selected) {
-                        if let Err(e) = self.start_game(*puzzle_id as u32) {
-                            eprintln!("Failed to start game: {}", e);
+                if let Some(puzzle_id) = self.selected_archive_id() {
+                    // continue a saved game rather than wiping it
+                    let started = if self.saved_games.contains(&puzzle_id) {
+                        self.resume_game(&puzzle_id)
+                    } else {
+                        self.start_game(&puzzle_id).map_err(|e| e.to_string())
+                    };
+                    if let Err(e) = started {
+                        self.show_error("Cannot start game", e);
+                    }
+                }
+            }
+            KeyCode::Char('n') => {
+                if let Some(puzzle_id) = self.selected_archive_id() {
+                    if let Err(e) = self.start_game(&puzzle_id) {
+                        self.show_error("Cannot start game", e);
+                    }
+                }
+            }
+            KeyCode::Char('l') => {
+                if let Some(puzzle_id) = self.selected_archive_id() {
+                    match self.archive.puzzle(&puzzle_id) {
+                        Ok(puzzle) => self.popup = Some(share_popup(&puzzle.id, &puzzle.board())),
+                        Err(e) => self.show_error("Cannot load puzzle", e),
+                    }
+                }
+            }
+            KeyCode::Char('t') => {
+                if let Some(puzzle_id) = self.selected_archive_id() {
+                    match self.archive.puzzle(&puzzle_id) {
+                        Ok(puzzle) => {
+                            self.popup = Some(text_popup(&puzzle.id, &puzzle.board(), &[]))
                         }
+                        Err(e) => self.show_error("Cannot load puzzle", e),
                     }
                 }
             }
@@ -252,36 +677,155 @@ impl App {
         }
     }
 
-    pub fn filtered_archive_items(&self) -> Vec<(usize, String)> {
-        let all: Vec<(usize, String)> =
-            (1..=750).map(|i| (i, format!("Puzzle {:03}", i))).collect();
-        if let Some(ref filter) = self.archive_filter {
-            if filter.is_empty() {
-                all
-            } else {
-                all.into_iter()
-                    .filter(|(i, _)| format!("{:03}", i).starts_with(filter))
-                    .collect()
+    pub fn selected_archive_id(&self) -> Option<PuzzleId> {
+        let selected = self.archive_list.selected()?;
+        let mut filtered = self.filtered_archive_items();
+        if selected >= filtered.len() {
+            return None;
+        }
+        filtered.swap_remove(selected).0
+    }
+
+    /// The preview for the highlighted archive entry, loaded on first use.
+    pub fn selected_preview(&mut self) -> Option<&Result<Preview, String>> {
+        let puzzle_id = self.selected_archive_id()?;
+        let archive = &self.archive;
+        Some(
+            self.previews
+                .entry(puzzle_id.clone())
+                .or_insert_with(|| Preview::load(archive, &puzzle_id)),
+        )
+    }
+
+    /// The archive list as shown: one row per matching puzzle, under a
+    /// heading for each section once the library has any puzzles in it.
+    /// Headings have no ID.
+    pub fn filtered_archive_items(&self) -> Vec<(Option<PuzzleId>, String)> {
+        let has_library = self
+            .archive
+            .entries
+            .iter()
+            .any(|entry| matches!(entry.id, PuzzleId::Library(_)));
+        let mut items = Vec::new();
+        let mut section = None;
+        for entry in &self.archive.entries {
+            let status = self
+                .stats
+                .status(&entry.id, self.saved_games.contains(&entry.id));
+            if !self.archive_query.matches(entry, status) {
+                continue;
             }
-        } else {
-            all
+
+            let in_library = matches!(entry.id, PuzzleId::Library(_));
+            if has_library && section != Some(in_library) {
+                let heading = if in_library {
+                    "📚 Library"
+                } else {
+                    "🗄 Archive"
+                };
+                items.push((None, heading.to_string()));
+                section = Some(in_library);
+            }
+
+            let best = self
+                .stats
+                .best_secs(&entry.id)
+                .map_or(String::new(), |secs| {
+                    format!(" {:02}:{:02}", secs / 60, secs % 60)
+                });
+            let name = if in_library {
+                entry.id.to_string()
+            } else {
+                format!("Puzzle {}", entry.id)
+            };
+            items.push((
+                Some(entry.id.clone()),
+                format!(
+                    "{} {} {}x{}{}",
+                    name,
+                    status.marker(),
+                    entry.rows,
+                    entry.cols,
+                    best
+                ),
+            ));
         }
+        items
     }
 
-    fn handle_settings_events(&mut self, key: KeyEvent) {
+    fn handle_generate_events(&mut self, key: KeyEvent) {
+        if self.generation.is_some() {
+            if let KeyCode::Char('q') | KeyCode::Esc = key.code {
+                self.cancel_generation();
+            }
+            return;
+        }
+        let options = &mut self.generator_options;
         match key.code {
             KeyCode::Char('q') => self.current_screen = CurrentScreen::Menu,
+            KeyCode::Up => self.generator_field = self.generator_field.saturating_sub(1),
+            KeyCode::Down => self.generator_field = (self.generator_field + 1).min(2),
+            KeyCode::Left => match self.generator_field {
+                0 => options.rows = (options.rows - 1).max(3),
+                1 => options.cols = (options.cols - 1).max(3),
+                _ => options.wall_percent = (options.wall_percent - 5).max(5),
+            },
+            KeyCode::Right => match self.generator_field {
+                0 => options.rows = (options.rows + 1).min(40),
+                1 => options.cols = (options.cols + 1).min(40),
+                _ => options.wall_percent = (options.wall_percent + 5).min(60),
+            },
+            KeyCode::Enter => self.start_generated_game(),
             _ => {}
         }
     }
 
-    fn handle_help_events(&mut self, key: KeyEvent) {
+    fn handle_import_events(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('q') => self.current_screen = CurrentScreen::Menu,
+            KeyCode::Esc => {
+                self.import_input.clear();
+                self.import_error = None;
+                self.current_screen = CurrentScreen::Menu;
+            }
+            KeyCode::Enter => {
+                let started = pzpr::from_url(&self.import_input).and_then(|puzzle| {
+                    self.start_imported_game(puzzle, &[])
+                        .map_err(|e| e.to_string())
+                });
+                match started {
+                    Ok(()) => {
+                        self.import_input.clear();
+                        self.import_error = None;
+                    }
+                    Err(e) => self.import_error = Some(e),
+                }
+            }
+            KeyCode::Backspace => {
+                self.import_input.pop();
+            }
+            KeyCode::Char(c) => self.import_input.push(c),
             _ => {}
         }
     }
 
+    fn handle_statistics_events(&mut self, key: KeyEvent) {
+        if let KeyCode::Char('q') = key.code {
+            self.current_screen = CurrentScreen::Menu;
+        }
+    }
+
+    fn handle_settings_events(&mut self, key: KeyEvent) {
+        if let KeyCode::Char('q') = key.code {
+            self.current_screen = CurrentScreen::Menu;
+        }
+    }
+
+    fn handle_help_events(&mut self, key: KeyEvent) {
+        if let KeyCode::Char('q') = key.code {
+            self.current_screen = CurrentScreen::Menu;
+        }
+    }
+
     fn handle_exiting_events(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Char('q') => self.current_screen = CurrentScreen::Menu,
@@ -290,10 +834,36 @@ impl App {
         }
     }
 
-    fn handle_win_events(&mut self, key: KeyEvent) {
+    fn handle_paused_events(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('q') => self.current_screen = CurrentScreen::Menu,
-            _ => {}
+            KeyCode::Char('q') => self.leave_game(),
+            _ => self.resume(),
+        }
+    }
+
+    fn handle_win_events(&mut self, key: KeyEvent) {
+        if let KeyCode::Char('q') = key.code {
+            self.current_screen = CurrentScreen::Menu;
         }
     }
 }
+
+// Popup showing the puzz.link URL for a board so it can be copied out.
+fn share_popup(puzzle_id: &PuzzleId, board: &[Vec<CellType>]) -> Popup {
+    Popup::info(
+        format!("Puzzle {} on puzz.link", puzzle_id),
+        pzpr::to_url(board),
+    )
+}
+
+// Popup showing a board, and any bulbs on it, in the text notation.
+fn text_popup(
+    puzzle_id: &PuzzleId,
+    board: &[Vec<CellType>],
+    objects: &[Vec<PlayerObject>],
+) -> Popup {
+    Popup::info(
+        format!("Puzzle {} as text", puzzle_id),
+        notation::to_text(board, objects),
+    )
+}
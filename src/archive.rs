@@ -0,0 +1,618 @@
+use crate::error::{PuzzleError, PuzzleErrorKind};
+use crate::game::{self, CellType, Puzzle, PuzzleId, PuzzleMetadata};
+use crate::logic::{self, Difficulty};
+use rand::seq::IndexedRandom;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Where the archive and the user's library live, their metadata (read once at
+// startup), and the search box's query language:
+// `author:Nikoli size:>=18 cols:24 unsolved`.
+
+include!(concat!(env!("OUT_DIR"), "/embedded_archive.rs"));
+
+/// Environment variable naming an archive directory to use instead of the
+/// built-in one.
+pub const ARCHIVE_ENV: &str = "AKARTUI_ARCHIVE";
+
+/// Environment variable naming the user's puzzle library directory.
+pub const LIBRARY_ENV: &str = "AKARTUI_LIBRARY";
+
+/// Where puzzle files are read from.
+#[derive(Debug, Clone)]
+pub enum ArchiveSource {
+    /// The archive compiled into the binary.
+    Embedded,
+    /// A directory laid out like `archive/`: `1/001.json`, `2/101.json`, ...
+    Directory(PathBuf),
+}
+
+impl ArchiveSource {
+    /// Pick the archive: the `--archive` flag, then `AKARTUI_ARCHIVE`, then
+    /// `archive = <dir>` in the config file, then the built-in copy. Naming a
+    /// directory that is not there is an error rather than an empty archive.
+    pub fn resolve(flag: Option<PathBuf>) -> Result<ArchiveSource, String> {
+        let chosen = flag
+            .map(|dir| ("--archive", dir))
+            .or_else(|| env::var_os(ARCHIVE_ENV).map(|dir| (ARCHIVE_ENV, PathBuf::from(dir))))
+            .or_else(|| configured("archive").map(|dir| ("the config file", dir)));
+        let Some((origin, dir)) = chosen else {
+            return Ok(ArchiveSource::Embedded);
+        };
+        if !dir.is_dir() {
+            return Err(format!(
+                "{} names {}, which is not a directory",
+                origin,
+                dir.display()
+            ));
+        }
+        Ok(ArchiveSource::Directory(dir))
+    }
+
+    /// Every puzzle file, as paths relative to the archive root.
+    pub fn files(&self) -> Vec<String> {
+        match self {
+            ArchiveSource::Embedded => EMBEDDED_ARCHIVE
+                .iter()
+                .map(|(path, _)| path.to_string())
+                .collect(),
+            ArchiveSource::Directory(dir) => {
+                let mut files = Vec::new();
+                collect_files(dir, "", &mut files);
+                files
+            }
+        }
+    }
+
+    /// Read the file at `relative`, e.g. `1/001.json`.
+    pub fn read(&self, relative: &str) -> io::Result<String> {
+        match self {
+            ArchiveSource::Embedded => EMBEDDED_ARCHIVE
+                .binary_search_by_key(&relative, |(path, _)| *path)
+                .map(|found| EMBEDDED_ARCHIVE[found].1.to_string())
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::NotFound, "not in the built-in archive")
+                }),
+            ArchiveSource::Directory(dir) => fs::read_to_string(dir.join(relative)),
+        }
+    }
+
+    /// The file at `relative`, as the user would find it.
+    pub fn file_name(&self, relative: &str) -> String {
+        match self {
+            ArchiveSource::Embedded => format!("archive/{}", relative),
+            ArchiveSource::Directory(dir) => dir.join(relative).display().to_string(),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/akartui-rs/config`, falling back to `~/.config`.
+pub fn config_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("akartui-rs").join("config"))
+}
+
+/// Where saves, stats and the library live: `$XDG_DATA_HOME/akartui-rs`,
+/// falling back to `~/.local/share`, or the current directory without either.
+pub fn data_dir() -> PathBuf {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .map_or_else(|| PathBuf::from("."), |base| base.join("akartui-rs"))
+}
+
+/// The library directory: the `--library` flag, then `AKARTUI_LIBRARY`, then
+/// `library = <dir>` in the config file, then `library/` in [`data_dir`].
+pub fn resolve_library(flag: Option<PathBuf>) -> PathBuf {
+    flag.or_else(|| env::var_os(LIBRARY_ENV).map(PathBuf::from))
+        .or_else(|| configured("library"))
+        .unwrap_or_else(|| data_dir().join("library"))
+}
+
+// The `<name> = <dir>` line of the config file, if there is one.
+fn configured(name: &str) -> Option<PathBuf> {
+    let text = fs::read_to_string(config_path()?).ok()?;
+    text.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == name).then(|| PathBuf::from(value.trim()))
+    })
+}
+
+// Walk `dir` for `.json` files, recording their paths under `prefix`.
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, &relative, files);
+        } else if relative.ends_with(".json") {
+            files.push(relative);
+        }
+    }
+}
+
+/// Archive files are named after their ID: `7/612.json` is puzzle 612.
+pub fn id_from_path(relative: &str) -> Option<PuzzleId> {
+    let name = relative.rsplit('/').next()?;
+    name.strip_suffix(".json")?
+        .parse()
+        .ok()
+        .map(PuzzleId::Archive)
+}
+
+// Library files are named however the user likes; the name is the ID.
+fn library_files(dir: &Path) -> Vec<(PuzzleId, String)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let file = entry.file_name().into_string().ok()?;
+            let name = file.strip_suffix(".json")?.to_string();
+            Some((PuzzleId::Library(name), file))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub id: PuzzleId,
+    /// Relative to the archive root or the library directory.
+    pub path: String,
+    pub author: String,
+    pub solver: String,
+    pub source: String,
+    pub info: String,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+#[derive(Debug)]
+pub struct ArchiveIndex {
+    pub source: ArchiveSource,
+    pub library: PathBuf,
+    /// Archive puzzles by number, then library puzzles by name.
+    pub entries: Vec<ArchiveEntry>,
+    /// Files that were left out because they could not be read.
+    pub errors: Vec<PuzzleError>,
+}
+
+// Only the metadata is needed; serde skips the grids.
+#[derive(Deserialize)]
+struct MetadataOnly {
+    metadata: PuzzleMetadata,
+}
+
+impl ArchiveIndex {
+    /// Index every puzzle file in `source` and `library`. Archive files that
+    /// are not named by ID are left out, and so are files that do not parse,
+    /// with an entry in `errors`.
+    pub fn load(source: ArchiveSource, library: PathBuf) -> ArchiveIndex {
+        let mut index = ArchiveIndex {
+            source,
+            library,
+            entries: Vec::new(),
+            errors: Vec::new(),
+        };
+        let archive_files = index
+            .source
+            .files()
+            .into_iter()
+            .filter_map(|path| Some((id_from_path(&path)?, path)));
+        let files: Vec<(PuzzleId, String)> =
+            archive_files.chain(library_files(&index.library)).collect();
+
+        for (id, path) in files {
+            let read = index
+                .read(&id, &path)
+                .map_err(PuzzleError::from)
+                .and_then(|text| {
+                    game::parse_puzzle_file::<MetadataOnly>(&text, &game::METADATA_FIELDS)
+                });
+            let metadata = match read {
+                Ok(MetadataOnly { metadata }) => metadata,
+                Err(e) => {
+                    index.errors.push(e.in_file(index.file_name(&id, &path)));
+                    continue;
+                }
+            };
+            index.entries.push(ArchiveEntry {
+                id,
+                path,
+                author: metadata.author,
+                solver: metadata.solver,
+                source: metadata.source,
+                info: metadata.info,
+                rows: metadata.size.rows,
+                cols: metadata.size.cols,
+            });
+        }
+        index.entries.sort_by(|a, b| a.id.cmp(&b.id));
+        index.entries.dedup_by(|a, b| a.id == b.id);
+        index
+    }
+
+    fn read(&self, puzzle_id: &PuzzleId, path: &str) -> io::Result<String> {
+        match puzzle_id {
+            PuzzleId::Library(_) => fs::read_to_string(self.library.join(path)),
+            _ => self.source.read(path),
+        }
+    }
+
+    pub fn entry(&self, puzzle_id: &PuzzleId) -> Option<&ArchiveEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.id.cmp(puzzle_id))
+            .ok()
+            .map(|found| &self.entries[found])
+    }
+
+    // The file at `path` for `puzzle_id`, as the user would find it.
+    fn file_name(&self, puzzle_id: &PuzzleId, path: &str) -> String {
+        match puzzle_id {
+            PuzzleId::Library(_) => self.library.join(path).display().to_string(),
+            _ => self.source.file_name(path),
+        }
+    }
+
+    /// Read and validate puzzle `puzzle_id` in full.
+    pub fn puzzle(&self, puzzle_id: &PuzzleId) -> Result<Puzzle, PuzzleError> {
+        let entry = self
+            .entry(puzzle_id)
+            .ok_or_else(|| PuzzleErrorKind::NotInArchive(puzzle_id.clone()))?;
+        self.read(puzzle_id, &entry.path)
+            .map_err(PuzzleError::from)
+            .and_then(|text| Puzzle::from_json(puzzle_id.clone(), &text))
+            .map_err(|e| e.in_file(self.file_name(puzzle_id, &entry.path)))
+    }
+
+    /// An archive puzzle picked at random from the ones that exist.
+    pub fn random_id(&self) -> Option<PuzzleId> {
+        let archive: Vec<&ArchiveEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.id, PuzzleId::Archive(_)))
+            .collect();
+        archive
+            .choose(&mut rand::rng())
+            .map(|entry| entry.id.clone())
+    }
+}
+
+/// What the archive preview shows for one puzzle.
+#[derive(Debug)]
+pub struct Preview {
+    pub puzzle: Puzzle,
+    pub clues: usize,
+    pub difficulty: Option<Difficulty>,
+}
+
+impl Preview {
+    pub fn load(archive: &ArchiveIndex, puzzle_id: &PuzzleId) -> Result<Preview, String> {
+        let puzzle = archive.puzzle(puzzle_id).map_err(|e| e.to_string())?;
+        let board = puzzle.board();
+        let clues = board
+            .iter()
+            .flatten()
+            .filter(|cell| matches!(cell, CellType::Target(_)))
+            .count();
+        Ok(Preview {
+            difficulty: logic::rate_difficulty(&board),
+            puzzle,
+            clues,
+        })
+    }
+}
+
+/// Where the player stands on one archive puzzle.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PuzzleStatus {
+    Unsolved,
+    InProgress,
+    Solved,
+    SolvedWithHints,
+}
+
+impl PuzzleStatus {
+    pub fn marker(&self) -> &'static str {
+        match self {
+            PuzzleStatus::Unsolved => "⬜",
+            PuzzleStatus::InProgress => "💾",
+            PuzzleStatus::Solved => "✅",
+            PuzzleStatus::SolvedWithHints => "💡",
+        }
+    }
+
+    pub fn is_keyword(word: &str) -> bool {
+        PuzzleStatus::Unsolved.matches_keyword(word).is_some()
+    }
+
+    /// Whether the filter keyword `word` covers this status; `None` if
+    /// `word` is not a status keyword at all.
+    pub fn matches_keyword(&self, word: &str) -> Option<bool> {
+        let matched = match word {
+            "unsolved" => matches!(self, PuzzleStatus::Unsolved | PuzzleStatus::InProgress),
+            "new" => *self == PuzzleStatus::Unsolved,
+            "progress" | "in-progress" | "saved" => *self == PuzzleStatus::InProgress,
+            "solved" => matches!(self, PuzzleStatus::Solved | PuzzleStatus::SolvedWithHints),
+            "clean" => *self == PuzzleStatus::Solved,
+            "hinted" | "assisted" => *self == PuzzleStatus::SolvedWithHints,
+            _ => return None,
+        };
+        Some(matched)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Cmp {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    fn holds(self, left: usize, right: usize) -> bool {
+        match self {
+            Cmp::Eq => left == right,
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Text {
+    Author,
+    Solver,
+    Source,
+    Info,
+    Any,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Id(String),
+    // true for library puzzles, false for the archive
+    Library(bool),
+    Status(String),
+    Contains(Text, String),
+    Rows(Cmp, usize),
+    Cols(Cmp, usize),
+    // both sides compared against the same number
+    Size(Cmp, usize),
+    Dimensions(usize, usize),
+}
+
+/// A parsed search: every term has to match.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+impl Query {
+    /// Parse a query such as `author:"Iwa Daigeki" size:10 unsolved`. Bare
+    /// numbers match ID prefixes, bare status words match progress, and any
+    /// other bare word is looked up in the author, solver, source and info.
+    pub fn parse(input: &str) -> Result<Query, String> {
+        let mut terms = Vec::new();
+        for word in split_words(input)? {
+            let term = match word.split_once(':') {
+                Some((key, value)) => parse_field(&key.to_lowercase(), value)?,
+                None if word.chars().all(|c| c.is_ascii_digit()) => Term::Id(word),
+                None if PuzzleStatus::is_keyword(&word.to_lowercase()) => {
+                    Term::Status(word.to_lowercase())
+                }
+                None => Term::Contains(Text::Any, word.to_lowercase()),
+            };
+            terms.push(term);
+        }
+        Ok(Query { terms })
+    }
+
+    pub fn matches(&self, entry: &ArchiveEntry, status: PuzzleStatus) -> bool {
+        self.terms.iter().all(|term| match term {
+            Term::Id(prefix) => entry.id.to_string().starts_with(prefix.as_str()),
+            Term::Library(wanted) => matches!(entry.id, PuzzleId::Library(_)) == *wanted,
+            Term::Status(word) => status.matches_keyword(word).unwrap_or(false),
+            Term::Contains(field, needle) => {
+                let fields = match field {
+                    Text::Author => vec![&entry.author],
+                    Text::Solver => vec![&entry.solver],
+                    Text::Source => vec![&entry.source],
+                    Text::Info => vec![&entry.info],
+                    Text::Any => vec![&entry.author, &entry.solver, &entry.source, &entry.info],
+                };
+                fields
+                    .iter()
+                    .any(|text| text.to_lowercase().contains(needle.as_str()))
+            }
+            Term::Rows(cmp, n) => cmp.holds(entry.rows, *n),
+            Term::Cols(cmp, n) => cmp.holds(entry.cols, *n),
+            Term::Size(cmp, n) => cmp.holds(entry.rows, *n) && cmp.holds(entry.cols, *n),
+            Term::Dimensions(rows, cols) => entry.rows == *rows && entry.cols == *cols,
+        })
+    }
+}
+
+// Split on whitespace, keeping double-quoted stretches together.
+fn split_words(input: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if quoted {
+        return Err("unclosed quote".to_string());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn parse_field(key: &str, value: &str) -> Result<Term, String> {
+    if value.is_empty() {
+        return Err(format!("{}: needs a value", key));
+    }
+    let text = |field| Ok(Term::Contains(field, value.to_lowercase()));
+    match key {
+        "id" => Ok(Term::Id(value.to_string())),
+        "in" => match value.to_lowercase().as_str() {
+            "library" => Ok(Term::Library(true)),
+            "archive" => Ok(Term::Library(false)),
+            _ => Err(format!("in: expected library or archive, got {}", value)),
+        },
+        "status" | "is" if PuzzleStatus::is_keyword(&value.to_lowercase()) => {
+            Ok(Term::Status(value.to_lowercase()))
+        }
+        "status" | "is" => Err(format!("unknown status: {}", value)),
+        "author" => text(Text::Author),
+        "solver" => text(Text::Solver),
+        "source" => text(Text::Source),
+        "info" => text(Text::Info),
+        "rows" => parse_number(key, value).map(|(cmp, n)| Term::Rows(cmp, n)),
+        "cols" => parse_number(key, value).map(|(cmp, n)| Term::Cols(cmp, n)),
+        "size" => match value.split_once(['x', 'X']) {
+            Some((rows, cols)) => match (rows.parse(), cols.parse()) {
+                (Ok(rows), Ok(cols)) => Ok(Term::Dimensions(rows, cols)),
+                _ => Err(format!("size: expected ROWSxCOLS, got {}", value)),
+            },
+            None => parse_number(key, value).map(|(cmp, n)| Term::Size(cmp, n)),
+        },
+        _ => Err(format!("unknown field: {}", key)),
+    }
+}
+
+// `18`, `=18`, `>18`, `>=18`, `<18` or `<=18`.
+fn parse_number(key: &str, value: &str) -> Result<(Cmp, usize), String> {
+    let (cmp, number) = if let Some(rest) = value.strip_prefix(">=") {
+        (Cmp::Ge, rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (Cmp::Le, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (Cmp::Gt, rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (Cmp::Lt, rest)
+    } else {
+        (Cmp::Eq, value.strip_prefix('=').unwrap_or(value))
+    };
+    number
+        .parse()
+        .map(|n| (cmp, n))
+        .map_err(|_| format!("{}: expected a number, got {}", key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(rows: usize, cols: usize, author: &str) -> ArchiveEntry {
+        ArchiveEntry {
+            id: PuzzleId::Archive(42),
+            path: "042.json".to_string(),
+            author: author.to_string(),
+            solver: String::new(),
+            source: "Janko".to_string(),
+            info: String::new(),
+            rows,
+            cols,
+        }
+    }
+
+    fn matches(query: &str, entry: &ArchiveEntry) -> bool {
+        Query::parse(query)
+            .unwrap()
+            .matches(entry, PuzzleStatus::Unsolved)
+    }
+
+    #[test]
+    fn size_comparison() {
+        let query = Query::parse("size:>=18").unwrap();
+        assert!(matches!(query.terms[..], [Term::Size(Cmp::Ge, 18)]));
+        assert!(matches("size:>=18", &entry(18, 20, "")));
+        assert!(!matches("size:>=18", &entry(17, 20, "")));
+        assert!(matches("size:<10", &entry(8, 9, "")));
+        assert!(matches("rows:=10 cols:12", &entry(10, 12, "")));
+    }
+
+    #[test]
+    fn exact_dimensions() {
+        let query = Query::parse("size:10x18").unwrap();
+        assert!(matches!(query.terms[..], [Term::Dimensions(10, 18)]));
+        assert!(matches("size:10x18", &entry(10, 18, "")));
+        assert!(!matches("size:10x18", &entry(18, 10, "")));
+        assert!(Query::parse("size:10xten").is_err());
+    }
+
+    #[test]
+    fn quoted_text() {
+        let query = Query::parse(r#"author:"Iwa Daigeki" janko"#).unwrap();
+        assert!(matches!(
+            &query.terms[..],
+            [Term::Contains(Text::Author, author), Term::Contains(Text::Any, any)]
+                if author == "iwa daigeki" && any == "janko"
+        ));
+        assert!(matches(
+            r#"author:"Iwa Daigeki""#,
+            &entry(10, 10, "Iwa Daigeki")
+        ));
+        assert!(!matches(r#"author:"Iwa Daigeki""#, &entry(10, 10, "Iwa")));
+        assert!(Query::parse(r#"author:"Iwa"#).is_err());
+    }
+
+    #[test]
+    fn bare_words() {
+        assert!(matches("04", &entry(10, 10, "")));
+        assert!(!matches("05", &entry(10, 10, "")));
+        assert!(matches("unsolved", &entry(10, 10, "")));
+        assert!(!matches("solved", &entry(10, 10, "")));
+    }
+
+    #[test]
+    fn unknown_fields_and_values() {
+        assert_eq!(
+            Query::parse("colour:red").unwrap_err(),
+            "unknown field: colour"
+        );
+        assert!(Query::parse("status:lost").is_err());
+        assert!(Query::parse("rows:many").is_err());
+        assert!(Query::parse("author:").is_err());
+    }
+
+    #[test]
+    fn archive_flag_must_name_a_directory() {
+        let missing = env::temp_dir().join("akartui-no-such-archive");
+        let error = ArchiveSource::resolve(Some(missing)).unwrap_err();
+        assert!(error.contains("--archive"), "{}", error);
+
+        let found = ArchiveSource::resolve(Some(env::temp_dir())).unwrap();
+        assert!(matches!(found, ArchiveSource::Directory(_)));
+    }
+}
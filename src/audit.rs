@@ -0,0 +1,113 @@
+use akartui_rs::archive::{self, ArchiveSource};
+use akartui_rs::game::{self, Puzzle, PuzzleId};
+use akartui_rs::solver::{self, Uniqueness};
+use std::collections::BTreeMap;
+
+// `akartui-rs audit`: check every archive file without starting the TUI.
+// Problems are printed one per line, prefixed with the file they concern.
+
+/// Audit the archive in `source`, printing each problem. Returns whether
+/// the archive is clean.
+pub fn run(source: &ArchiveSource) -> bool {
+    let mut problems = Vec::new();
+    let mut by_number: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    let mut files = source.files();
+    files.sort();
+    if files.is_empty() {
+        // an unreadable directory looks the same from here
+        problems.push(format!("{}: no puzzle files found", source.file_name("")));
+    }
+
+    for relative in &files {
+        let file = source.file_name(relative);
+        let Some(PuzzleId::Archive(number)) = archive::id_from_path(relative) else {
+            problems.push(format!("{}: not named after a puzzle number", file));
+            continue;
+        };
+        by_number.entry(number).or_default().push(file.clone());
+
+        let puzzle = source
+            .read(relative)
+            .map_err(Into::into)
+            .and_then(|text| Puzzle::from_json(PuzzleId::Archive(number), &text));
+        match puzzle {
+            Ok(puzzle) => problems.extend(
+                check_puzzle(&puzzle)
+                    .into_iter()
+                    .map(|problem| format!("{}: {}", file, problem)),
+            ),
+            Err(e) => problems.push(e.in_file(file).to_string()),
+        }
+    }
+
+    let last = by_number.keys().next_back().copied().unwrap_or(0);
+    for number in 1..=last {
+        match by_number.get(&number).map(Vec::as_slice) {
+            None => problems.push(format!("puzzle {:03} is missing", number)),
+            Some([_]) => {}
+            Some(files) => problems.push(format!(
+                "puzzle {:03} appears more than once: {}",
+                number,
+                files.join(", ")
+            )),
+        }
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if last == 0 {
+        println!(
+            "Checked {} files: {} problem(s)",
+            files.len(),
+            problems.len()
+        );
+    } else {
+        println!(
+            "Checked {} files, puzzles 001-{:03}: {} problem(s)",
+            files.len(),
+            last,
+            problems.len()
+        );
+    }
+    problems.is_empty()
+}
+
+// What is wrong with a puzzle that parsed: its stored solution breaks the
+// rules, or the problem does not have exactly one solution.
+fn check_puzzle(puzzle: &Puzzle) -> Vec<String> {
+    let mut problems = Vec::new();
+    let board = puzzle.board();
+    match puzzle.solution_bulbs() {
+        Some(bulbs) => match game::rule_violations(&board, &bulbs) {
+            Some(violations) => problems.extend(
+                violations
+                    .iter()
+                    .map(|violation| format!("stored solution: {}", violation)),
+            ),
+            None => problems.push("stored solution does not fit the problem".to_string()),
+        },
+        None => problems.push("no stored solution".to_string()),
+    }
+    match solver::uniqueness(&board) {
+        Uniqueness::Unique => {}
+        Uniqueness::NoSolution => problems.push("the problem has no solution".to_string()),
+        Uniqueness::Multiple => problems.push("the problem has more than one solution".to_string()),
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn empty_archive_is_a_problem() {
+        let dir = std::env::temp_dir().join(format!("akartui-audit-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let clean = run(&ArchiveSource::Directory(dir.clone()));
+        fs::remove_dir_all(dir).unwrap();
+        assert!(!clean);
+    }
+}
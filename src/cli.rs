@@ -0,0 +1,244 @@
+use akartui_rs::archive::{ArchiveIndex, ArchiveSource};
+use akartui_rs::error::PuzzleError;
+use akartui_rs::game::{CellType, Game, PlayerObject, Puzzle, PuzzleId};
+use akartui_rs::notation;
+use akartui_rs::pzpr;
+use akartui_rs::solver::Uniqueness;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The headless subcommands, for scripts and CI: they load a puzzle, run the
+// game logic on it and print the result without touching the terminal.
+
+/// A subcommand that runs without the TUI.
+pub enum Command {
+    /// Print the solution grid.
+    Solve(String),
+    /// Check a candidate answer against the puzzle's rules.
+    Validate { puzzle: String, solution: String },
+    /// Print the board as text.
+    Render(String),
+    /// Print the puzzle's metadata.
+    Info(String),
+}
+
+/// Run `command` and return the process exit code: 0 on success, 1 if the
+/// answer is wrong or there is no solution, 2 if something could not be read.
+pub fn run(command: Command, source: ArchiveSource, library: PathBuf) -> i32 {
+    let result = match command {
+        Command::Solve(target) => {
+            find_puzzle(&target, source, library).and_then(|(puzzle, _)| solve(puzzle))
+        }
+        Command::Validate { puzzle, solution } => find_puzzle(&puzzle, source, library)
+            .and_then(|(puzzle, _)| validate(puzzle, &solution)),
+        Command::Render(target) => {
+            find_puzzle(&target, source, library).and_then(|(puzzle, bulbs)| render(puzzle, &bulbs))
+        }
+        Command::Info(target) => {
+            find_puzzle(&target, source, library).and_then(|(puzzle, _)| info(puzzle))
+        }
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+/// A puzzle named on the command line: a JSON puzzle file, a text grid, or
+/// failing those a puzz.link URL. Bulbs drawn in a text grid come along.
+pub fn open_puzzle(arg: &str) -> Result<(Puzzle, Vec<(usize, usize)>), String> {
+    let path = Path::new(arg);
+    if !path.is_file() {
+        let puzzle = pzpr::from_url(arg).map_err(|e| format!("cannot import URL: {}", e))?;
+        return Ok((puzzle, Vec::new()));
+    }
+    let opened = fs::read_to_string(path)
+        .map_err(PuzzleError::from)
+        .and_then(|text| {
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let puzzle = Puzzle::from_json(PuzzleId::Imported, &text)?;
+                Ok((puzzle, Vec::new()))
+            } else {
+                let mut grid = notation::parse(&text)?;
+                grid.puzzle.metadata.info = arg.to_string();
+                Ok((grid.puzzle, grid.bulbs))
+            }
+        });
+    opened.map_err(|e| e.in_file(arg).to_string())
+}
+
+// Like `open_puzzle`, but an archive number or library name comes first.
+fn find_puzzle(
+    target: &str,
+    source: ArchiveSource,
+    library: PathBuf,
+) -> Result<(Puzzle, Vec<(usize, usize)>), String> {
+    if Path::new(target).is_file() {
+        return open_puzzle(target);
+    }
+    let id = match target.parse() {
+        Ok(number) => PuzzleId::Archive(number),
+        Err(_) => PuzzleId::Library(target.to_string()),
+    };
+    let index = ArchiveIndex::load(source, library);
+    if index.entry(&id).is_some() {
+        let puzzle = index.puzzle(&id).map_err(|e| e.to_string())?;
+        return Ok((puzzle, Vec::new()));
+    }
+    match id {
+        PuzzleId::Archive(_) => Err(format!("puzzle {} is not in the archive", id)),
+        _ => open_puzzle(target).map_err(|_| {
+            format!(
+                "{}: not a file, a library puzzle or a puzz.link URL",
+                target
+            )
+        }),
+    }
+}
+
+// Start a game on `puzzle` with `bulbs` placed, the way the TUI would.
+fn load_game(puzzle: Puzzle, bulbs: &[(usize, usize)]) -> Result<Game, String> {
+    let mut game = Game::new();
+    game.load_puzzle(puzzle).map_err(|e| e.to_string())?;
+    for &(row, col) in bulbs {
+        game.player_objects[row][col] = PlayerObject::Lightbulb;
+    }
+    game.update();
+    Ok(game)
+}
+
+fn solve(puzzle: Puzzle) -> Result<i32, String> {
+    let game = load_game(puzzle, &[])?;
+    let Some(solution) = game.solve() else {
+        eprintln!("the puzzle has no solution");
+        return Ok(1);
+    };
+    let objects: Vec<Vec<PlayerObject>> = solution
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|bulb| match bulb {
+                    true => PlayerObject::Lightbulb,
+                    false => PlayerObject::Empty,
+                })
+                .collect()
+        })
+        .collect();
+    print!("{}", notation::to_text(&game.board, &objects));
+    if game.count_solutions(2) > 1 {
+        eprintln!("the puzzle has more than one solution; this is one of them");
+    }
+    Ok(0)
+}
+
+fn validate(puzzle: Puzzle, solution: &str) -> Result<i32, String> {
+    let (answer, bulbs) = read_answer(solution)?;
+    let answer = answer.board();
+    let board = puzzle.board();
+    if answer.len() != board.len() || answer[0].len() != board[0].len() {
+        return Err(format!(
+            "{}: the answer is {}x{}, but the puzzle is {}x{}",
+            solution,
+            answer.len(),
+            answer[0].len(),
+            board.len(),
+            board[0].len()
+        ));
+    }
+    for (i, (answer_row, board_row)) in answer.iter().zip(&board).enumerate() {
+        if let Some(j) = answer_row.iter().zip(board_row).position(|(a, b)| a != b) {
+            return Err(format!(
+                "{}: row {}, column {}: {:?} where the puzzle has {:?}",
+                solution,
+                i + 1,
+                j + 1,
+                answer_row[j].symbol(),
+                board_row[j].symbol()
+            ));
+        }
+    }
+
+    let game = load_game(puzzle, &bulbs)?;
+    let violations = game
+        .validate()
+        .ok_or_else(|| format!("{}: the answer does not fit the puzzle", solution))?;
+    for violation in &violations {
+        println!("{}", violation);
+    }
+    if violations.is_empty() {
+        println!("solved");
+        Ok(0)
+    } else {
+        Ok(1)
+    }
+}
+
+// A candidate answer and its bulbs: a text grid, or a JSON puzzle file whose
+// `solution` marks the bulbs.
+fn read_answer(path: &str) -> Result<(Puzzle, Vec<(usize, usize)>), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    if Path::new(path).extension().is_some_and(|ext| ext == "json") {
+        let puzzle = Puzzle::from_json(PuzzleId::Imported, &text)
+            .map_err(|e| e.in_file(path).to_string())?;
+        let Some(solution) = puzzle.solution_bulbs() else {
+            return Err(format!("{}: no solution in the file", path));
+        };
+        let bulbs = solution
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, bulb)| **bulb)
+                    .map(move |(j, _)| (i, j))
+            })
+            .collect();
+        Ok((puzzle, bulbs))
+    } else {
+        let grid = notation::parse(&text).map_err(|e| e.in_file(path).to_string())?;
+        Ok((grid.puzzle, grid.bulbs))
+    }
+}
+
+fn render(puzzle: Puzzle, bulbs: &[(usize, usize)]) -> Result<i32, String> {
+    let game = load_game(puzzle, bulbs)?;
+    print!("{}", notation::to_text(&game.board, &game.player_objects));
+    Ok(0)
+}
+
+fn info(puzzle: Puzzle) -> Result<i32, String> {
+    let game = load_game(puzzle, &[])?;
+    let Some(puzzle) = &game.puzzle else {
+        return Ok(2);
+    };
+    let meta = &puzzle.metadata;
+    let clues = game
+        .board
+        .iter()
+        .flatten()
+        .filter(|cell| matches!(cell, CellType::Target(_)))
+        .count();
+    let solutions = match game.uniqueness() {
+        Uniqueness::NoSolution => "none",
+        Uniqueness::Unique => "unique",
+        Uniqueness::Multiple => "more than one",
+    };
+    println!("ID: {}", puzzle.id);
+    println!("Type: {}", meta.puzzle_type);
+    println!("Author: {}", meta.author);
+    println!("Solver: {}", meta.solver);
+    println!("Source: {}", meta.source);
+    println!("Info: {}", meta.info);
+    println!("Size: {}x{}", meta.size.rows, meta.size.cols);
+    println!("Clues: {}", clues);
+    println!(
+        "Difficulty: {}",
+        game.difficulty()
+            .map_or("Unsolvable", |difficulty| difficulty.label())
+    );
+    println!("Solutions: {}", solutions);
+    Ok(0)
+}
@@ -0,0 +1,191 @@
+use crate::game::PuzzleId;
+use serde_json::error::Category;
+use std::fmt;
+use std::io;
+
+// Everything that can be wrong with a puzzle file. Positions are stored
+// ready to print, the way an editor numbers lines.
+
+/// Which grid of a puzzle file a position is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grid {
+    Problem,
+    Solution,
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Grid::Problem => write!(f, "problem"),
+            Grid::Solution => write!(f, "solution"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PuzzleErrorKind {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The puzzle is not in the archive index.
+    NotInArchive(PuzzleId),
+    /// Not valid JSON, or a field of the wrong type.
+    Json {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A required field, named by its path such as `metadata.size.rows`.
+    MissingField(String),
+    /// `metadata.size` or the grid itself has no cells.
+    EmptyGrid,
+    BadSymbol {
+        grid: Grid,
+        row: usize,
+        col: usize,
+        symbol: String,
+    },
+    /// A grid with a different number of rows than `metadata.size`.
+    RowCount {
+        grid: Grid,
+        expected: usize,
+        found: usize,
+    },
+    /// A grid row with a different number of cells than `metadata.size`.
+    RowLength {
+        grid: Grid,
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A solution cell that contradicts the problem, like a bulb on a wall.
+    SolutionMismatch {
+        row: usize,
+        col: usize,
+        problem: String,
+        solution: String,
+    },
+    /// A text grid that does not parse.
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for PuzzleErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleErrorKind::Io(e) => write!(f, "{}", e),
+            PuzzleErrorKind::NotInArchive(id) => write!(f, "puzzle {} is not in the archive", id),
+            PuzzleErrorKind::Json {
+                line,
+                column,
+                message,
+            }
+            | PuzzleErrorKind::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            PuzzleErrorKind::MissingField(field) => write!(f, "missing field `{}`", field),
+            PuzzleErrorKind::EmptyGrid => write!(f, "the puzzle has no cells"),
+            PuzzleErrorKind::BadSymbol {
+                grid,
+                row,
+                col,
+                symbol,
+            } => write!(
+                f,
+                "{} row {}, column {}: unknown symbol {:?}",
+                grid, row, col, symbol
+            ),
+            PuzzleErrorKind::RowCount {
+                grid,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} has {} rows, but metadata.size says {}",
+                grid, found, expected
+            ),
+            PuzzleErrorKind::RowLength {
+                grid,
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} row {} has {} cells, but metadata.size says {}",
+                grid, row, found, expected
+            ),
+            PuzzleErrorKind::SolutionMismatch {
+                row,
+                col,
+                problem,
+                solution,
+            } => write!(
+                f,
+                "solution row {}, column {}: {:?} where the problem has {:?}",
+                row, col, solution, problem
+            ),
+        }
+    }
+}
+
+/// A puzzle that could not be loaded, and the file it came from if known.
+#[derive(Debug)]
+pub struct PuzzleError {
+    pub file: Option<String>,
+    pub kind: PuzzleErrorKind,
+}
+
+impl PuzzleError {
+    /// Name the file the error was found in.
+    pub fn in_file(mut self, file: impl Into<String>) -> PuzzleError {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}: {}", file, self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+impl From<PuzzleErrorKind> for PuzzleError {
+    fn from(kind: PuzzleErrorKind) -> PuzzleError {
+        PuzzleError { file: None, kind }
+    }
+}
+
+impl From<io::Error> for PuzzleError {
+    fn from(e: io::Error) -> PuzzleError {
+        PuzzleErrorKind::Io(e).into()
+    }
+}
+
+impl From<serde_json::Error> for PuzzleError {
+    fn from(e: serde_json::Error) -> PuzzleError {
+        if e.classify() == Category::Io {
+            return PuzzleErrorKind::Io(e.into()).into();
+        }
+        let (line, column) = (e.line(), e.column());
+        // serde_json appends the position to every message
+        let message = e.to_string();
+        let message = message
+            .strip_suffix(&format!(" at line {} column {}", line, column))
+            .unwrap_or(&message);
+        PuzzleErrorKind::Json {
+            line,
+            column,
+            message: message.to_string(),
+        }
+        .into()
+    }
+}
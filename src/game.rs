@@ -1,8 +1,11 @@
 //use ratatui::widgets::{List, ListItem, ListState};
 
+use crate::error::{Grid, PuzzleError, PuzzleErrorKind};
+use crate::logic::{self, Difficulty, Hint};
+use crate::solver;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
+use std::sync::OnceLock;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PuzzleMetadata {
@@ -21,29 +24,293 @@ pub struct PuzzleSize {
     pub unit: u32,
 }
 
+/// Which puzzle this is: a number in the bundled archive, a file in the
+/// user's library, a freshly generated board, or one opened from a URL.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum PuzzleId {
+    Archive(u32),
+    Library(String),
+    Generated,
+    Imported,
+}
+
+impl PuzzleId {
+    /// The text form used for save files and stats: `012`, `lib-<name>` or
+    /// `generated`.
+    pub fn key(&self) -> String {
+        match self {
+            PuzzleId::Archive(number) => format!("{:03}", number),
+            PuzzleId::Library(name) => format!("lib-{}", name),
+            PuzzleId::Generated => "generated".to_string(),
+            PuzzleId::Imported => "imported".to_string(),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<PuzzleId> {
+        if let Some(name) = key.strip_prefix("lib-") {
+            Some(PuzzleId::Library(name.to_string()))
+        } else if key == "generated" {
+            Some(PuzzleId::Generated)
+        } else if key == "imported" {
+            Some(PuzzleId::Imported)
+        } else {
+            key.parse().ok().map(PuzzleId::Archive)
+        }
+    }
+
+    /// Whether progress on this puzzle is worth saving and counting; generated
+    /// and imported boards have nothing to reopen them by.
+    pub fn is_tracked(&self) -> bool {
+        matches!(self, PuzzleId::Archive(_) | PuzzleId::Library(_))
+    }
+}
+
+impl std::fmt::Display for PuzzleId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuzzleId::Archive(number) => write!(f, "{:03}", number),
+            PuzzleId::Library(name) => write!(f, "{}", name),
+            PuzzleId::Generated => write!(f, "generated"),
+            PuzzleId::Imported => write!(f, "imported"),
+        }
+    }
+}
+
+impl From<PuzzleId> for String {
+    fn from(id: PuzzleId) -> String {
+        id.key()
+    }
+}
+
+impl TryFrom<String> for PuzzleId {
+    type Error = String;
+
+    fn try_from(key: String) -> Result<PuzzleId, String> {
+        PuzzleId::from_key(&key).ok_or_else(|| format!("not a puzzle ID: {}", key))
+    }
+}
+
 #[derive(Debug)]
 pub struct Puzzle {
-    pub id: u32,
+    pub id: PuzzleId,
     pub metadata: PuzzleMetadata,
     pub problem: Vec<Vec<String>>,
-    //pub solution: Vec<Vec<String>>,
+    pub solution: Option<Vec<Vec<String>>>,
+}
+
+// A puzzle file as it is on disk.
+#[derive(Deserialize)]
+struct PuzzleFile {
+    metadata: PuzzleMetadata,
+    problem: Vec<Vec<String>>,
+    solution: Option<Vec<Vec<String>>>,
+}
+
+// The fields a puzzle file has to have, checked before deserializing so a
+// missing one is reported by its path.
+pub(crate) const METADATA_FIELDS: [&str; 10] = [
+    "metadata",
+    "metadata.puzzle_type",
+    "metadata.author",
+    "metadata.solver",
+    "metadata.source",
+    "metadata.info",
+    "metadata.size",
+    "metadata.size.cols",
+    "metadata.size.rows",
+    "metadata.size.unit",
+];
+const GRID_FIELDS: [&str; 1] = ["problem"];
+
+/// Deserialize a puzzle file (or part of one) after checking that every
+/// field in `required` is present.
+pub(crate) fn parse_puzzle_file<T: DeserializeOwned>(
+    text: &str,
+    required: &[&str],
+) -> Result<T, PuzzleError> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    for field in required {
+        if field
+            .split('.')
+            .try_fold(&value, |value, key| value.get(key))
+            .is_none()
+        {
+            return Err(PuzzleErrorKind::MissingField(field.to_string()).into());
+        }
+    }
+    // parsed again from the text so type errors keep their position
+    Ok(serde_json::from_str(text)?)
+}
+
+const PROBLEM_SYMBOLS: [&str; 7] = ["x", "0", "1", "2", "3", "4", "-"];
+const SOLUTION_SYMBOLS: [&str; 8] = ["x", "0", "1", "2", "3", "4", "-", "o"];
+
+impl Puzzle {
+    /// Parse and validate a puzzle file in the archive's JSON layout.
+    pub fn from_json(puzzle_id: PuzzleId, text: &str) -> Result<Puzzle, PuzzleError> {
+        let required: Vec<&str> = METADATA_FIELDS
+            .iter()
+            .chain(&GRID_FIELDS)
+            .copied()
+            .collect();
+        let file: PuzzleFile = parse_puzzle_file(text, &required)?;
+        let puzzle = Puzzle {
+            id: puzzle_id,
+            metadata: file.metadata,
+            problem: file.problem,
+            solution: file.solution,
+        };
+        puzzle.validate()?;
+        Ok(puzzle)
+    }
+
+    /// Check that the grids match `metadata.size`, hold only known symbols,
+    /// and that the solution agrees with the problem.
+    pub fn validate(&self) -> Result<(), PuzzleError> {
+        let size = &self.metadata.size;
+        if size.rows == 0 || size.cols == 0 {
+            return Err(PuzzleErrorKind::EmptyGrid.into());
+        }
+        check_grid(Grid::Problem, &self.problem, size, &PROBLEM_SYMBOLS)?;
+        let Some(solution) = &self.solution else {
+            return Ok(());
+        };
+        check_grid(Grid::Solution, solution, size, &SOLUTION_SYMBOLS)?;
+        for (i, (problem_row, solution_row)) in self.problem.iter().zip(solution).enumerate() {
+            for (j, (problem, solution)) in problem_row.iter().zip(solution_row).enumerate() {
+                // empty cells get `-` or a bulb; walls and clues are either
+                // copied over or written as a plain `x` or `-`
+                let fits = if problem == "-" {
+                    solution == "-" || solution == "o"
+                } else {
+                    solution == problem || solution == "x" || solution == "-"
+                };
+                if !fits {
+                    return Err(PuzzleErrorKind::SolutionMismatch {
+                        row: i + 1,
+                        col: j + 1,
+                        problem: problem.clone(),
+                        solution: solution.clone(),
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The problem grid as cell types.
+    pub fn board(&self) -> Vec<Vec<CellType>> {
+        self.problem
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell.as_str() {
+                        "x" => CellType::Wall,
+                        "0" => CellType::Target(0),
+                        "1" => CellType::Target(1),
+                        "2" => CellType::Target(2),
+                        "3" => CellType::Target(3),
+                        "4" => CellType::Target(4),
+                        _ => CellType::Empty,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The stored solution's bulbs, where `true` marks an `o`.
+    pub fn solution_bulbs(&self) -> Option<Vec<Vec<bool>>> {
+        let solution = self.solution.as_ref()?;
+        Some(
+            solution
+                .iter()
+                .map(|row| row.iter().map(|cell| cell == "o").collect())
+                .collect(),
+        )
+    }
+
+    /// Serialize in the same layout as the archive files.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let mut file = serde_json::json!({
+            "metadata": self.metadata,
+            "problem": self.problem,
+        });
+        if let Some(solution) = &self.solution {
+            file["solution"] = serde_json::json!(solution);
+        }
+        serde_json::to_string_pretty(&file)
+    }
+}
+
+// Check one grid's shape against `size` and its cells against `symbols`.
+fn check_grid(
+    grid: Grid,
+    cells: &[Vec<String>],
+    size: &PuzzleSize,
+    symbols: &[&str],
+) -> Result<(), PuzzleError> {
+    if cells.len() != size.rows {
+        return Err(PuzzleErrorKind::RowCount {
+            grid,
+            expected: size.rows,
+            found: cells.len(),
+        }
+        .into());
+    }
+    for (i, row) in cells.iter().enumerate() {
+        if row.len() != size.cols {
+            return Err(PuzzleErrorKind::RowLength {
+                grid,
+                row: i + 1,
+                expected: size.cols,
+                found: row.len(),
+            }
+            .into());
+        }
+        if let Some(j) = row
+            .iter()
+            .position(|cell| !symbols.contains(&cell.as_str()))
+        {
+            return Err(PuzzleErrorKind::BadSymbol {
+                grid,
+                row: i + 1,
+                col: j + 1,
+                symbol: row[j].clone(),
+            }
+            .into());
+        }
+    }
+    Ok(())
 }
 
 #[derive(PartialEq)]
 pub enum GameState {
     Ready,
     Playing,
-    //Paused,
+    Paused,
     GameOver,
 }
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CellType {
     Wall,
     Target(u8),
     Empty,
 }
 
+impl CellType {
+    /// The cell as written in puzzle files: `x`, `0`-`4` or `-`.
+    pub fn symbol(&self) -> String {
+        match self {
+            CellType::Wall => "x".to_string(),
+            CellType::Target(n) => n.to_string(),
+            CellType::Empty => "-".to_string(),
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum LightState {
     IsWall,
@@ -57,7 +324,7 @@ impl LightState {
     }
 }
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum PlayerObject {
     IsWall,
     Lightbulb,
@@ -69,12 +336,149 @@ pub enum PlayerObject {
 pub enum CellDisplay {
     Wall,
     Target(u8),
+    // clue with this many bulbs too many
+    TargetOverfilled(u8),
+    // clue still missing this many bulbs, with too few free sides left
+    TargetUnreachable(u8),
     LightBulb,
+    // bulb that shines on another bulb
+    LightBulbConflict,
     Light(u8),
     Flag,
     Dark,
 }
 
+/// A broken Akari rule, as reported by `Game::validate`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RuleViolation {
+    UnlitCell((usize, usize)),
+    BulbsSeeEachOther((usize, usize), (usize, usize)),
+    TooManyBulbs {
+        at: (usize, usize),
+        expected: u8,
+        found: u8,
+    },
+    TooFewBulbs {
+        at: (usize, usize),
+        expected: u8,
+        found: u8,
+    },
+}
+
+/// The `(rows, cols)` of a grid whose rows all have the same length, or
+/// `None` for a ragged one.
+pub(crate) fn grid_size<T>(grid: &[Vec<T>]) -> Option<(usize, usize)> {
+    let cols = grid.first().map_or(0, Vec::len);
+    grid.iter()
+        .all(|row| row.len() == cols)
+        .then_some((grid.len(), cols))
+}
+
+/// A board position as players read it: rows and columns from 1, the way
+/// puzzle file errors count them too.
+pub(crate) fn fmt_pos((row, col): (usize, usize)) -> String {
+    format!("row {}, column {}", row + 1, col + 1)
+}
+
+impl std::fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let at = fmt_pos;
+        match *self {
+            RuleViolation::UnlitCell(pos) => write!(f, "unlit cell at {}", at(pos)),
+            RuleViolation::BulbsSeeEachOther(a, b) => {
+                write!(f, "bulbs at {} and {} see each other", at(a), at(b))
+            }
+            RuleViolation::TooManyBulbs {
+                at: pos,
+                expected,
+                found,
+            }
+            | RuleViolation::TooFewBulbs {
+                at: pos,
+                expected,
+                found,
+            } => write!(f, "clue {} at {} has {} bulbs", expected, at(pos), found),
+        }
+    }
+}
+
+/// Every Akari rule that `bulbs` (`true` marks a bulb) break on `board`, or
+/// `None` if the two grids are not the same rectangular shape.
+pub fn rule_violations(board: &[Vec<CellType>], bulbs: &[Vec<bool>]) -> Option<Vec<RuleViolation>> {
+    let (rows, cols) = grid_size(board)?;
+    if grid_size(bulbs)? != (rows, cols) {
+        return None;
+    }
+    let is_bulb = |i: usize, j: usize| bulbs[i][j];
+    let mut lit = vec![vec![false; cols]; rows];
+    let mut violations = Vec::new();
+
+    for i in 0..rows {
+        for j in 0..cols {
+            if !is_bulb(i, j) {
+                continue;
+            }
+            lit[i][j] = true;
+            for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (mut ni, mut nj) = (i as isize + di, j as isize + dj);
+                while ni >= 0 && ni < rows as isize && nj >= 0 && nj < cols as isize {
+                    let (ui, uj) = (ni as usize, nj as usize);
+                    if board[ui][uj] != CellType::Empty {
+                        break;
+                    }
+                    lit[ui][uj] = true;
+                    // report each pair once, from the bulb nearer the top left
+                    if is_bulb(ui, uj) && di + dj > 0 {
+                        violations.push(RuleViolation::BulbsSeeEachOther((i, j), (ui, uj)));
+                    }
+                    ni += di;
+                    nj += dj;
+                }
+            }
+        }
+    }
+
+    for (i, board_row) in board.iter().enumerate() {
+        for (j, cell) in board_row.iter().enumerate() {
+            match *cell {
+                CellType::Empty if !lit[i][j] => {
+                    violations.push(RuleViolation::UnlitCell((i, j)));
+                }
+                CellType::Target(expected) => {
+                    let mut found = 0;
+                    for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                        let ni = i as isize + di;
+                        let nj = j as isize + dj;
+                        if ni >= 0
+                            && ni < rows as isize
+                            && nj >= 0
+                            && nj < cols as isize
+                            && is_bulb(ni as usize, nj as usize)
+                        {
+                            found += 1;
+                        }
+                    }
+                    if found > expected {
+                        violations.push(RuleViolation::TooManyBulbs {
+                            at: (i, j),
+                            expected,
+                            found,
+                        });
+                    } else if found < expected {
+                        violations.push(RuleViolation::TooFewBulbs {
+                            at: (i, j),
+                            expected,
+                            found,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Some(violations)
+}
+
 pub enum PlayerOperation {
     AddLightbulb,
     AddFlag,
@@ -89,10 +493,59 @@ pub enum Direction {
     Right,
 }
 
-#[derive(Clone)]
-pub struct UndoSnapshot {
-    pub player_objects: Vec<Vec<PlayerObject>>,
-    pub cursor_position: (usize, usize),
+/// One change to a single cell, enough to play it forwards or backwards.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct Move {
+    pub position: (usize, usize),
+    pub before: PlayerObject,
+    pub after: PlayerObject,
+}
+
+/// The moves made so far; everything past `done` has been undone and can be
+/// redone until a new move replaces it.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct History {
+    pub moves: Vec<Move>,
+    pub done: usize,
+}
+
+impl History {
+    pub fn record(&mut self, change: Move) {
+        self.moves.truncate(self.done);
+        self.moves.push(change);
+        self.done += 1;
+    }
+
+    pub fn undo(&mut self) -> Option<Move> {
+        self.done = self.done.checked_sub(1)?;
+        Some(self.moves[self.done])
+    }
+
+    pub fn redo(&mut self) -> Option<Move> {
+        let change = *self.moves.get(self.done)?;
+        self.done += 1;
+        Some(change)
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.clear();
+        self.done = 0;
+    }
+}
+
+/// How often the player leaned on the game's help during one attempt.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct AssistLog {
+    pub hints: u32,
+    pub checks: u32,
+    pub cell_reveals: u32,
+    pub solution_reveals: u32,
+}
+
+impl AssistLog {
+    pub fn is_clean(&self) -> bool {
+        *self == AssistLog::default()
+    }
 }
 
 pub struct Game {
@@ -104,7 +557,23 @@ pub struct Game {
     //pub display: Vec<Vec<CellDisplay>>,
     pub target_remain: Vec<Vec<Option<i8>>>,
     pub cursor_position: (usize, usize),
-    pub undo_stack: Vec<UndoSnapshot>,
+    pub history: History,
+    /// Moves taken back during this attempt, counting each one undone.
+    pub undos: u32,
+    pub hint: Option<Hint>,
+    pub check: Option<Vec<(usize, usize)>>,
+    pub show_solution: bool,
+    pub assists: AssistLog,
+    // rated on first use, as it is slow on the largest boards
+    difficulty: OnceLock<Option<Difficulty>>,
+    // left unsolved until check, reveal or the solution view asks for it
+    solution: OnceLock<Option<Vec<Vec<bool>>>>,
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
 }
 
 impl Game {
@@ -119,145 +588,212 @@ impl Game {
             target_remain: Vec::new(),
             cursor_position: (0, 0),
             //player_position_state: Vec::new(),
-            undo_stack: Vec::new(),
+            history: History::default(),
+            undos: 0,
+            hint: None,
+            check: None,
+            show_solution: false,
+            assists: AssistLog::default(),
+            difficulty: OnceLock::new(),
+            solution: OnceLock::new(),
         }
     }
 
-    pub fn init_game(&mut self, puzzle_id: u32) -> Result<(), Box<dyn std::error::Error>> {
-        // Calculate which archive folder to look in
-        let folder = ((puzzle_id - 1) / 100 + 1).to_string();
-        let file_path = format!("archive/{}/{:03}.json", folder, puzzle_id);
-
-        // Read puzzle file
-        let puzzle_data = fs::read_to_string(Path::new(&file_path))?;
-
-        // Parse JSON data
-        let puzzle_json: serde_json::Value = serde_json::from_str(&puzzle_data)?;
-
-        // Extract metadata
-        let metadata: PuzzleMetadata = serde_json::from_value(puzzle_json["metadata"].clone())?;
-
-        // Extract problem and solution
-        let problem: Vec<Vec<String>> = serde_json::from_value(puzzle_json["problem"].clone())?;
-        //let solution: Vec<Vec<String>> = serde_json::from_value(puzzle_json["solution"].clone())?;
-
-        // Initialize player position state
-        //let player_position_state = vec![ListState::default(); 1];
-
-        // Create puzzle
-        self.puzzle = Some(Puzzle {
-            id: puzzle_id,
-            metadata,
-            problem,
-            //solution,
-        });
+    /// Start playing an already-parsed puzzle, such as a generated one. The
+    /// puzzle is checked first, as for a file, and left out if it is invalid.
+    pub fn load_puzzle(&mut self, puzzle: Puzzle) -> Result<(), PuzzleError> {
+        puzzle.validate()?;
+        self.puzzle = Some(puzzle);
 
         // Initialize board
         self.init_board();
 
-        self.undo_stack.clear();
-
+        self.history.clear();
+        self.undos = 0;
+        self.hint = None;
+        self.check = None;
+        self.show_solution = false;
+        self.assists = AssistLog::default();
+        self.difficulty = OnceLock::new();
+        self.solution = OnceLock::new();
         Ok(())
     }
 
+    /// How hard the loaded puzzle is, or `None` if it cannot be solved.
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        *self
+            .difficulty
+            .get_or_init(|| logic::rate_difficulty(&self.board))
+    }
+
     fn init_board(&mut self) {
-        if let Some(puzzle) = &self.puzzle {
-            let rows = puzzle.metadata.size.rows;
-            let cols = puzzle.metadata.size.cols;
-
-            // Initialize board with empty cells
-            self.board = vec![vec![CellType::Empty; cols]; rows];
-            self.light_state = vec![vec![LightState::Dark; cols]; rows];
-            self.player_objects = vec![vec![PlayerObject::Empty; cols]; rows];
-
-            // Set up initial board state based on puzzle problem
-            for (i, row) in puzzle.problem.iter().enumerate() {
-                for (j, cell) in row.iter().enumerate() {
-                    match cell.as_str() {
-                        "x" => {
-                            self.board[i][j] = CellType::Wall;
-                            self.light_state[i][j] = LightState::IsWall;
-                            self.player_objects[i][j] = PlayerObject::IsWall;
-                        }
-                        "0" => {
-                            self.board[i][j] = CellType::Target(0);
-                            self.light_state[i][j] = LightState::IsWall;
-                            self.player_objects[i][j] = PlayerObject::IsWall;
-                        }
-                        "1" => {
-                            self.board[i][j] = CellType::Target(1);
-                            self.light_state[i][j] = LightState::IsWall;
-                            self.player_objects[i][j] = PlayerObject::IsWall;
-                        }
-                        "2" => {
-                            self.board[i][j] = CellType::Target(2);
-                            self.light_state[i][j] = LightState::IsWall;
-                            self.player_objects[i][j] = PlayerObject::IsWall;
-                        }
-                        "3" => {
-                            self.board[i][j] = CellType::Target(3);
-                            self.light_state[i][j] = LightState::IsWall;
-                            self.player_objects[i][j] = PlayerObject::IsWall;
-                        }
-                        "4" => {
-                            self.board[i][j] = CellType::Target(4);
-                            self.light_state[i][j] = LightState::IsWall;
-                            self.player_objects[i][j] = PlayerObject::IsWall;
-                        }
-                        _ => {
-                            self.board[i][j] = CellType::Empty;
-                            self.player_objects[i][j] = PlayerObject::Empty;
-                        }
-                    }
-                }
-            }
-            self.target_remain = vec![vec![None; cols]; rows];
-            for (i, row) in puzzle.problem.iter().enumerate() {
-                for (j, cell) in row.iter().enumerate() {
-                    match cell.as_str() {
-                        "0" => self.target_remain[i][j] = Some(0),
-                        "1" => self.target_remain[i][j] = Some(1),
-                        "2" => self.target_remain[i][j] = Some(2),
-                        "3" => self.target_remain[i][j] = Some(3),
-                        "4" => self.target_remain[i][j] = Some(4),
-                        _ => {}
-                    }
-                }
-            }
-        }
+        let Some(puzzle) = &self.puzzle else {
+            return;
+        };
+        self.board = puzzle.board();
+        self.light_state = self
+            .board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        CellType::Empty => LightState::Dark,
+                        _ => LightState::IsWall,
+                    })
+                    .collect()
+            })
+            .collect();
+        self.player_objects = self
+            .board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        CellType::Empty => PlayerObject::Empty,
+                        _ => PlayerObject::IsWall,
+                    })
+                    .collect()
+            })
+            .collect();
+        self.target_remain = self
+            .board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        CellType::Target(n) => Some(*n as i8),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
     }
 
     pub fn start(&mut self) {
         self.state = GameState::Playing;
         //panic!()
     }
+    pub fn pause(&mut self) {
+        if self.state == GameState::Playing {
+            self.state = GameState::Paused;
+        }
+    }
+    pub fn resume(&mut self) {
+        if self.state == GameState::Paused {
+            self.state = GameState::Playing;
+        }
+    }
     pub fn quit(&mut self) {
         //self.state = GameState::GameOver;
     }
 
     pub fn check_win(&self) -> bool {
-        for i in 0..self.board.len() {
-            for j in 0..self.board[0].len() {
-                match self.board[i][j] {
-                    CellType::Empty => {
-                        if self.light_state[i][j] == LightState::Dark {
-                            return false;
-                        }
-                    }
-                    CellType::Target(_) | CellType::Wall => {}
+        !self.board.is_empty() && self.validate().is_some_and(|found| found.is_empty())
+    }
+
+    /// Check the player's bulbs against every Akari rule, working from
+    /// `board` and `player_objects` alone so stale lighting cannot hide a problem.
+    /// Returns `None` if `player_objects` has been given a different shape.
+    pub fn validate(&self) -> Option<Vec<RuleViolation>> {
+        let bulbs: Vec<Vec<bool>> = self
+            .player_objects
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|obj| *obj == PlayerObject::Lightbulb)
+                    .collect()
+            })
+            .collect();
+        rule_violations(&self.board, &bulbs)
+    }
+
+    /// Solve the loaded board from scratch, ignoring the player's placements.
+    /// Returns a grid where `true` marks a bulb, or `None` if no solution exists.
+    pub fn solve(&self) -> Option<Vec<Vec<bool>>> {
+        solver::solve(&self.board)
+    }
+
+    /// Count solutions of the loaded board, stopping once `limit` are found.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        solver::count_solutions(&self.board, limit)
+    }
+
+    /// Whether the loaded board is a well-formed puzzle with exactly one solution.
+    pub fn uniqueness(&self) -> solver::Uniqueness {
+        solver::uniqueness(&self.board)
+    }
+
+    /// Ask for the next logical deduction from the player's current placements.
+    pub fn request_hint(&mut self) {
+        self.check = None;
+        self.hint = logic::next_hint(&self.board, &self.player_objects);
+        self.assists.hints += 1;
+    }
+
+    /// Bulb layout of the answer: the puzzle's stored solution when it ships
+    /// with one, otherwise whatever the solver finds on first use.
+    pub fn solution(&self) -> Option<&Vec<Vec<bool>>> {
+        self.solution
+            .get_or_init(|| {
+                self.puzzle
+                    .as_ref()
+                    .and_then(Puzzle::solution_bulbs)
+                    .or_else(|| self.solve())
+            })
+            .as_ref()
+    }
+
+    /// Mark every bulb that is not in the solution and every flag on a cell
+    /// that needs a bulb, without revealing anything else.
+    pub fn check_progress(&mut self) {
+        self.hint = None;
+        let Some(solution) = self.solution() else {
+            self.check = None;
+            return;
+        };
+        let mut wrong = Vec::new();
+        for (i, row) in self.player_objects.iter().enumerate() {
+            for (j, object) in row.iter().enumerate() {
+                match object {
+                    PlayerObject::Lightbulb if !solution[i][j] => wrong.push((i, j)),
+                    PlayerObject::Flag if solution[i][j] => wrong.push((i, j)),
+                    _ => {}
                 }
             }
         }
-        for i in 0..self.board.len() {
-            for j in 0..self.board[0].len() {
-                if let Some(remain) = self.target_remain[i][j] {
-                    if remain != 0 {
-                        return false;
-                    }
-                }
-            }
+        self.check = Some(wrong);
+        self.assists.checks += 1;
+    }
+
+    /// Put the solution's answer for the cell under the cursor on the board:
+    /// a bulb if it needs one, otherwise a flag.
+    pub fn reveal_cell(&mut self) {
+        let (row, col) = self.cursor_position;
+        if !self.cursor_on_board() || self.board[row][col] != CellType::Empty {
+            return;
+        }
+        let Some(needs_bulb) = self.solution().map(|solution| solution[row][col]) else {
+            return;
+        };
+        let answer = if needs_bulb {
+            PlayerObject::Lightbulb
+        } else {
+            PlayerObject::Flag
+        };
+        if self.set_object(answer) {
+            self.assists.cell_reveals += 1;
+        }
+    }
+
+    /// Show or hide the full solution on top of the player's own placements.
+    pub fn toggle_solution(&mut self) {
+        if self.solution().is_none() {
+            return;
+        }
+        self.show_solution = !self.show_solution;
+        if self.show_solution {
+            self.assists.solution_reveals += 1;
         }
-        true
     }
 
     pub fn update(&mut self) {
@@ -378,7 +914,34 @@ impl Game {
         self.light_state[row][col] = LightState::light(4);
     }
 
-    fn display_priority(&self, row: usize, col: usize) -> CellDisplay {
+    // Neighbours of a clue that could still take a bulb: not flagged and not lit.
+    fn free_sides(&self, row: usize, col: usize) -> usize {
+        let mut free = 0;
+        for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let ni = row as isize + di;
+            let nj = col as isize + dj;
+            if ni >= 0
+                && ni < self.board.len() as isize
+                && nj >= 0
+                && nj < self.board[0].len() as isize
+            {
+                let (ni, nj) = (ni as usize, nj as usize);
+                if self.player_objects[ni][nj] == PlayerObject::Empty
+                    && self.light_state[ni][nj] == LightState::Dark
+                {
+                    free += 1;
+                }
+            }
+        }
+        free
+    }
+
+    fn display_priority(
+        &self,
+        row: usize,
+        col: usize,
+        conflicts: &[(usize, usize)],
+    ) -> CellDisplay {
         //// Wall has highest priority
         if self.board[row][col] == CellType::Wall {
             return CellDisplay::Wall;
@@ -386,11 +949,20 @@ impl Game {
         // Target numbers next
         if let CellType::Target(_orig) = self.board[row][col] {
             if let Some(remain) = self.target_remain[row][col] {
+                if remain < 0 {
+                    return CellDisplay::TargetOverfilled(remain.unsigned_abs());
+                }
+                if remain as usize > self.free_sides(row, col) {
+                    return CellDisplay::TargetUnreachable(remain as u8);
+                }
                 return CellDisplay::Target(remain as u8);
             }
         } else
         // Light bulbs placed by player
         if self.player_objects[row][col] == PlayerObject::Lightbulb {
+            if conflicts.contains(&(row, col)) {
+                return CellDisplay::LightBulbConflict;
+            }
             return CellDisplay::LightBulb;
         } else
         // Light level from propagation
@@ -407,16 +979,27 @@ impl Game {
     }
 
     pub fn get_display(&self) -> Vec<Vec<CellDisplay>> {
-        let mut display = vec![vec![CellDisplay::Dark; self.board[0].len()]; self.board.len()];
-        for i in 0..self.board.len() {
-            for j in 0..self.board[0].len() {
-                display[i][j] = self.display_priority(i, j);
-            }
-        }
-        display
+        let conflicts: Vec<(usize, usize)> = self
+            .validate()
+            .into_iter()
+            .flatten()
+            .filter_map(|violation| match violation {
+                RuleViolation::BulbsSeeEachOther(a, b) => Some([a, b]),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        (0..self.board.len())
+            .map(|i| {
+                (0..self.board[0].len())
+                    .map(|j| self.display_priority(i, j, &conflicts))
+                    .collect()
+            })
+            .collect()
     }
 
     pub fn player_move_cursor(&mut self, direction: Direction) {
+        let (rows, cols) = (self.board.len(), self.board.first().map_or(0, Vec::len));
         match direction {
             Direction::Up => {
                 if self.cursor_position.0 > 0 {
@@ -424,7 +1007,7 @@ impl Game {
                 }
             }
             Direction::Down => {
-                if self.cursor_position.0 < self.board.len() - 1 {
+                if self.cursor_position.0 + 1 < rows {
                     self.cursor_position.0 += 1;
                 }
             }
@@ -434,39 +1017,129 @@ impl Game {
                 }
             }
             Direction::Right => {
-                if self.cursor_position.1 < self.board[0].len() - 1 {
+                if self.cursor_position.1 + 1 < cols {
                     self.cursor_position.1 += 1;
                 }
             }
         }
     }
     pub fn undo(&mut self) {
-        if let Some(snapshot) = self.undo_stack.pop() {
-            self.player_objects = snapshot.player_objects;
-            self.cursor_position = snapshot.cursor_position;
+        if let Some(change) = self.history.undo() {
+            self.apply(change.position, change.before);
+            self.undos += 1;
             self.update(); // 重新計算 light_state、target_remain 等
         }
     }
-    pub fn push_undo(&mut self) {
-        self.undo_stack.push(UndoSnapshot {
-            player_objects: self.player_objects.clone(),
-            cursor_position: self.cursor_position,
+
+    pub fn redo(&mut self) {
+        if let Some(change) = self.history.redo() {
+            self.apply(change.position, change.after);
+            self.update();
+        }
+    }
+
+    /// Undo every move, back to the empty board.
+    pub fn undo_all(&mut self) {
+        while let Some(change) = self.history.undo() {
+            self.apply(change.position, change.before);
+            self.undos += 1;
+        }
+        self.update();
+    }
+
+    /// Redo every undone move.
+    pub fn redo_all(&mut self) {
+        while let Some(change) = self.history.redo() {
+            self.apply(change.position, change.after);
+        }
+        self.update();
+    }
+
+    fn apply(&mut self, (row, col): (usize, usize), object: PlayerObject) {
+        self.hint = None;
+        self.check = None;
+        self.player_objects[row][col] = object;
+        self.cursor_position = (row, col);
+    }
+
+    // There is nothing under the cursor until a puzzle is loaded.
+    fn cursor_on_board(&self) -> bool {
+        let (row, col) = self.cursor_position;
+        self.board.get(row).is_some_and(|cells| col < cells.len())
+            && self
+                .player_objects
+                .get(row)
+                .is_some_and(|objects| col < objects.len())
+    }
+
+    // Put `object` under the cursor and record it, unless nothing would change.
+    fn set_object(&mut self, object: PlayerObject) -> bool {
+        let (row, col) = self.cursor_position;
+        let before = self.player_objects[row][col];
+        if before == object {
+            return false;
+        }
+        self.history.record(Move {
+            position: (row, col),
+            before,
+            after: object,
         });
+        self.apply((row, col), object);
+        true
+    }
+
+    /// Put `object` at `position` as an undoable move and relight the board.
+    /// Unlike `player_operation` this skips the placement checks, so any bulb
+    /// layout can be tried. Returns false if `position` is off the board or a
+    /// wall, or already holds `object`.
+    pub fn place(&mut self, position: (usize, usize), object: PlayerObject) -> bool {
+        let (row, col) = position;
+        let open = self
+            .player_objects
+            .get(row)
+            .and_then(|row| row.get(col))
+            .is_some_and(|current| *current != PlayerObject::IsWall);
+        if !open || object == PlayerObject::IsWall {
+            return false;
+        }
+        self.cursor_position = position;
+        let placed = self.set_object(object);
+        self.update();
+        placed
+    }
+
+    /// Whether a bulb shines on `position`. Walls and cells off the board are
+    /// never lit.
+    pub fn is_lit(&self, (row, col): (usize, usize)) -> bool {
+        matches!(
+            self.light_state.get(row).and_then(|row| row.get(col)),
+            Some(LightState::Light(_))
+        )
     }
+
     pub fn player_operation(&mut self, operation: PlayerOperation) {
-        self.push_undo();
+        if !self.cursor_on_board() {
+            return;
+        }
+        let object = self.next_object(operation);
+        self.set_object(object);
+    }
+
+    // What `operation` turns the cell under the cursor into.
+    fn next_object(&self, operation: PlayerOperation) -> PlayerObject {
         let (row, col) = self.cursor_position;
+        let current = self.player_objects[row][col];
         match operation {
             PlayerOperation::AddLightbulb => {
-                match self.player_objects[row][col] {
+                match current {
                     PlayerObject::Lightbulb => {
                         //if empty
-                        self.player_objects[row][col] = PlayerObject::Empty;
+                        PlayerObject::Empty
                     }
                     PlayerObject::Empty => {
                         //only allow in dark
                         if self.light_state[row][col] != LightState::Dark {
-                            return;
+                            return current;
                         }
                         //check if target is already satisfied
                         for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
@@ -480,33 +1153,24 @@ impl Game {
                                 let (ni, nj) = (ni as usize, nj as usize);
                                 if let Some(remain) = self.target_remain[ni][nj] {
                                     if remain <= 0 {
-                                        self.player_objects[row][col] = PlayerObject::Flag;
-                                        return;
+                                        return PlayerObject::Flag;
                                     }
                                 }
                             }
                         }
-                        self.player_objects[row][col] = PlayerObject::Lightbulb;
-                    }
-                    PlayerObject::Flag => {
-                        self.player_objects[row][col] = PlayerObject::Empty;
+                        PlayerObject::Lightbulb
                     }
-                    _ => {}
+                    PlayerObject::Flag => PlayerObject::Empty,
+                    _ => current,
                 }
             }
-            PlayerOperation::AddFlag => match self.player_objects[row][col] {
-                PlayerObject::Empty => {
-                    if self.light_state[row][col] == LightState::Dark {
-                        self.player_objects[row][col] = PlayerObject::Flag;
-                    }
-                }
-                PlayerObject::Flag => {
-                    self.player_objects[row][col] = PlayerObject::Empty;
+            PlayerOperation::AddFlag => match current {
+                PlayerObject::Empty if self.light_state[row][col] == LightState::Dark => {
+                    PlayerObject::Flag
                 }
-                PlayerObject::Lightbulb => {
-                    self.player_objects[row][col] = PlayerObject::Empty;
-                }
-                _ => {}
+                PlayerObject::Flag => PlayerObject::Empty,
+                PlayerObject::Lightbulb => PlayerObject::Empty,
+                _ => current,
             },
             // PlayerOperation::RemoveLightbulb => {
             //     self.player_objects[row][col] = PlayerObject::Empty;
@@ -517,3 +1181,246 @@ impl Game {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = r#"{
+        "metadata": {
+            "puzzle_type": "akari", "author": "", "solver": "", "source": "", "info": "",
+            "size": { "cols": 2, "rows": 1, "unit": 25 }
+        },
+        "problem": [["-", "1"]],
+        "solution": [["o", "1"]]
+    }"#;
+
+    fn load_error(text: &str) -> PuzzleErrorKind {
+        Puzzle::from_json(PuzzleId::Imported, text)
+            .unwrap_err()
+            .kind
+    }
+
+    #[test]
+    fn loads_puzzle_file() {
+        let puzzle = Puzzle::from_json(PuzzleId::Imported, PUZZLE).unwrap();
+        assert_eq!(
+            puzzle.board(),
+            vec![vec![CellType::Empty, CellType::Target(1)]]
+        );
+    }
+
+    #[test]
+    fn load_puzzle_rejects_bad_size() {
+        let mut puzzle = Puzzle::from_json(PuzzleId::Imported, PUZZLE).unwrap();
+        puzzle.metadata.size.cols = 3;
+        let mut game = Game::new();
+        assert!(matches!(
+            game.load_puzzle(puzzle).unwrap_err().kind,
+            PuzzleErrorKind::RowLength { found: 2, .. }
+        ));
+        assert!(game.puzzle.is_none());
+    }
+
+    #[test]
+    fn names_missing_fields() {
+        let text = PUZZLE.replace(r#""rows": 1, "#, "");
+        assert!(matches!(
+            load_error(&text),
+            PuzzleErrorKind::MissingField(field) if field == "metadata.size.rows"
+        ));
+        let text = PUZZLE.replace(r#""problem": [["-", "1"]],"#, "");
+        assert!(matches!(
+            load_error(&text),
+            PuzzleErrorKind::MissingField(field) if field == "problem"
+        ));
+    }
+
+    #[test]
+    fn locates_json_errors() {
+        let text = PUZZLE.replace(r#""cols": 2"#, r#""cols": "2""#);
+        assert!(matches!(
+            load_error(&text),
+            PuzzleErrorKind::Json { line: 4, .. }
+        ));
+        assert!(matches!(
+            load_error("{\n  \"metadata\": ,"),
+            PuzzleErrorKind::Json {
+                line: 2,
+                column: 15,
+                ..
+            }
+        ));
+    }
+
+    fn step(col: usize) -> Move {
+        Move {
+            position: (0, col),
+            before: PlayerObject::Empty,
+            after: PlayerObject::Lightbulb,
+        }
+    }
+
+    #[test]
+    fn history_undo_and_redo() {
+        let mut history = History::default();
+        assert_eq!(history.undo(), None);
+        history.record(step(0));
+        history.record(step(1));
+        assert_eq!(history.undo(), Some(step(1)));
+        assert_eq!(history.undo(), Some(step(0)));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(step(0)));
+        assert_eq!(history.redo(), Some(step(1)));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.done, 2);
+    }
+
+    #[test]
+    fn history_record_drops_undone_moves() {
+        let mut history = History::default();
+        history.record(step(0));
+        history.record(step(1));
+        history.record(step(2));
+        history.undo();
+        history.undo();
+        history.record(step(3));
+        assert_eq!(history.moves, vec![step(0), step(3)]);
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(step(3)));
+        assert_eq!(history.undo(), Some(step(0)));
+    }
+
+    #[test]
+    fn history_clear() {
+        let mut history = History::default();
+        history.record(step(0));
+        history.clear();
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn game_can_be_shared_between_threads() {
+        fn is_sync<T: Sync>() {}
+        is_sync::<Game>();
+    }
+
+    fn board(text: &str) -> Vec<Vec<CellType>> {
+        crate::notation::parse(text).unwrap().puzzle.board()
+    }
+
+    // `o` marks a bulb, anything else none
+    fn bulbs(text: &str) -> Vec<Vec<bool>> {
+        text.lines()
+            .map(|line| line.split(' ').map(|cell| cell == "o").collect())
+            .collect()
+    }
+
+    #[test]
+    fn solved_board_breaks_no_rules() {
+        let board = board("- 1 -\nx - -");
+        assert_eq!(
+            rule_violations(&board, &bulbs("o 1 -\nx - o")),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn reports_unlit_cells() {
+        let board = board("- x -");
+        assert_eq!(
+            rule_violations(&board, &bulbs("o x -")),
+            Some(vec![RuleViolation::UnlitCell((0, 2))])
+        );
+    }
+
+    #[test]
+    fn reports_each_pair_of_bulbs_once() {
+        let board = board("- - -\n- x -");
+        assert_eq!(
+            rule_violations(&board, &bulbs("o - o\no x o")),
+            Some(vec![
+                RuleViolation::BulbsSeeEachOther((0, 0), (1, 0)),
+                RuleViolation::BulbsSeeEachOther((0, 0), (0, 2)),
+                RuleViolation::BulbsSeeEachOther((0, 2), (1, 2)),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_clue_counts() {
+        let board = board("- 1 -\n- 2 -");
+        assert_eq!(
+            rule_violations(&board, &bulbs("o 1 o\n- 2 -")),
+            Some(vec![
+                RuleViolation::TooManyBulbs {
+                    at: (0, 1),
+                    expected: 1,
+                    found: 2
+                },
+                RuleViolation::TooFewBulbs {
+                    at: (1, 1),
+                    expected: 2,
+                    found: 0
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn no_win_while_bulbs_see_each_other() {
+        let mut game = Game::new();
+        let puzzle = crate::notation::parse("- -").unwrap().puzzle;
+        game.load_puzzle(puzzle).unwrap();
+        game.place((0, 0), PlayerObject::Lightbulb);
+        assert!(game.check_win());
+
+        game.place((0, 1), PlayerObject::Lightbulb);
+        assert!(game
+            .light_state
+            .iter()
+            .flatten()
+            .all(|light| *light != LightState::Dark));
+        assert!(!game.check_win());
+    }
+
+    #[test]
+    fn solves_only_when_the_solution_is_needed() {
+        let mut game = Game::new();
+        let puzzle = crate::notation::parse("- 1\n- x").unwrap().puzzle;
+        game.load_puzzle(puzzle).unwrap();
+        assert!(game.solution.get().is_none());
+
+        game.check_progress();
+        assert_eq!(game.check, Some(Vec::new()));
+        assert_eq!(
+            game.solution(),
+            Some(&vec![vec![true, false], vec![false, false]])
+        );
+    }
+
+    #[test]
+    fn mismatched_shapes_are_not_checked() {
+        let board = board("- -\n- -");
+        assert_eq!(rule_violations(&board, &bulbs("o -")), None);
+        assert_eq!(rule_violations(&board, &bulbs("o -\n-")), None);
+    }
+
+    #[test]
+    fn cursor_stays_put_without_a_puzzle() {
+        let mut game = Game::new();
+        for direction in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            game.player_move_cursor(direction);
+        }
+        assert_eq!(game.cursor_position, (0, 0));
+        game.player_operation(PlayerOperation::AddLightbulb);
+        game.reveal_cell();
+        assert!(!game.check_win());
+    }
+}
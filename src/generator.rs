@@ -0,0 +1,274 @@
+use crate::game::{CellType, Puzzle, PuzzleId, PuzzleMetadata, PuzzleSize};
+use crate::solver::{self, Uniqueness};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+// Puzzle generator: scatter walls, light the board greedily to get a valid
+// answer, number every wall from that answer, add walls until the answer is
+// the only one, then strip clues for as long as it stays unique.
+
+#[derive(Debug, Clone, Copy)]
+pub struct GeneratorOptions {
+    pub rows: usize,
+    pub cols: usize,
+    /// Chance, in percent, that a cell starts out as a wall.
+    pub wall_percent: u8,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            rows: 10,
+            cols: 10,
+            wall_percent: 20,
+        }
+    }
+}
+
+/// Shared with a generator running on another thread: how far it has got,
+/// and a flag that stops it at the next solver call.
+#[derive(Debug, Default)]
+pub struct Progress {
+    cancelled: AtomicBool,
+    walls_added: AtomicUsize,
+    clues_checked: AtomicUsize,
+    clues: AtomicUsize,
+}
+
+impl Progress {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Walls added so far to rule out other answers.
+    pub fn walls_added(&self) -> usize {
+        self.walls_added.load(Ordering::Relaxed)
+    }
+
+    /// Clues tried for removal so far, out of how many; `(0, 0)` until the
+    /// generator gets that far.
+    pub fn clues(&self) -> (usize, usize) {
+        (
+            self.clues_checked.load(Ordering::Relaxed),
+            self.clues.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Build a new puzzle with exactly one solution. The same options and seed
+/// always give the same puzzle.
+pub fn generate(options: GeneratorOptions, seed: u64) -> Puzzle {
+    generate_with_progress(options, seed, &Progress::default())
+        .expect("generation is only stopped by cancelling")
+}
+
+/// Like [`generate`], reporting to `progress` as it goes; `None` if it was
+/// cancelled. Large boards with few walls can take a long time.
+pub fn generate_with_progress(
+    options: GeneratorOptions,
+    seed: u64,
+    progress: &Progress,
+) -> Option<Puzzle> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let rows = options.rows.max(1);
+    let cols = options.cols.max(1);
+
+    // Every wall starts out numbered; the numbers are filled in from the bulbs.
+    let mut board = vec![vec![CellType::Empty; cols]; rows];
+    for cell in board.iter_mut().flatten() {
+        if rng.random_range(0..100) < options.wall_percent {
+            *cell = CellType::Target(0);
+        }
+    }
+    let mut bulbs = vec![vec![false; cols]; rows];
+    fill_bulbs(&board, &mut bulbs, &mut rng);
+    number_walls(&mut board, &bulbs);
+
+    // Where another answer puts a bulb we don't, a wall rules that answer out.
+    loop {
+        if progress.is_cancelled() {
+            return None;
+        }
+        let found = solver::find_solutions(&board, 2);
+        if found.len() < 2 {
+            break;
+        }
+        let Some(other) = found.iter().find(|solution| **solution != bulbs) else {
+            break;
+        };
+        let Some((i, j)) = (0..rows)
+            .flat_map(|i| (0..cols).map(move |j| (i, j)))
+            .find(|&(i, j)| other[i][j] && !bulbs[i][j])
+        else {
+            break;
+        };
+        board[i][j] = CellType::Target(0);
+        fill_bulbs(&board, &mut bulbs, &mut rng);
+        number_walls(&mut board, &bulbs);
+        progress.walls_added.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let mut clues: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|i| (0..cols).map(move |j| (i, j)))
+        .filter(|&(i, j)| matches!(board[i][j], CellType::Target(_)))
+        .collect();
+    clues.shuffle(&mut rng);
+    progress.clues.store(clues.len(), Ordering::Relaxed);
+    for (i, j) in clues {
+        if progress.is_cancelled() {
+            return None;
+        }
+        progress.clues_checked.fetch_add(1, Ordering::Relaxed);
+        let clue = board[i][j];
+        board[i][j] = CellType::Wall;
+        if solver::uniqueness(&board) != Uniqueness::Unique {
+            board[i][j] = clue;
+        }
+    }
+
+    Some(to_puzzle(&board, &bulbs, options, seed))
+}
+
+// Light every cell that is still dark, visiting cells in random order.
+fn fill_bulbs(board: &[Vec<CellType>], bulbs: &mut [Vec<bool>], rng: &mut StdRng) {
+    let rows = board.len();
+    let cols = board[0].len();
+    let mut lit = vec![vec![false; cols]; rows];
+    let mut cells = Vec::new();
+    for i in 0..rows {
+        for j in 0..cols {
+            if board[i][j] != CellType::Empty {
+                bulbs[i][j] = false;
+            } else {
+                cells.push((i, j));
+            }
+        }
+    }
+    for &(i, j) in &cells {
+        if bulbs[i][j] {
+            light_from(board, &mut lit, i, j);
+        }
+    }
+    cells.shuffle(rng);
+    for (i, j) in cells {
+        if !lit[i][j] {
+            bulbs[i][j] = true;
+            light_from(board, &mut lit, i, j);
+        }
+    }
+}
+
+fn light_from(board: &[Vec<CellType>], lit: &mut [Vec<bool>], row: usize, col: usize) {
+    lit[row][col] = true;
+    for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+        let (mut ni, mut nj) = (row as isize + di, col as isize + dj);
+        while ni >= 0 && ni < board.len() as isize && nj >= 0 && nj < board[0].len() as isize {
+            let (ui, uj) = (ni as usize, nj as usize);
+            if board[ui][uj] != CellType::Empty {
+                break;
+            }
+            lit[ui][uj] = true;
+            ni += di;
+            nj += dj;
+        }
+    }
+}
+
+fn number_walls(board: &mut [Vec<CellType>], bulbs: &[Vec<bool>]) {
+    for i in 0..board.len() {
+        for j in 0..board[0].len() {
+            if let CellType::Target(_) = board[i][j] {
+                let mut count = 0;
+                for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let ni = i as isize + di;
+                    let nj = j as isize + dj;
+                    if ni >= 0
+                        && ni < board.len() as isize
+                        && nj >= 0
+                        && nj < board[0].len() as isize
+                        && bulbs[ni as usize][nj as usize]
+                    {
+                        count += 1;
+                    }
+                }
+                board[i][j] = CellType::Target(count);
+            }
+        }
+    }
+}
+
+fn to_puzzle(
+    board: &[Vec<CellType>],
+    bulbs: &[Vec<bool>],
+    options: GeneratorOptions,
+    seed: u64,
+) -> Puzzle {
+    let problem: Vec<Vec<String>> = board
+        .iter()
+        .map(|row| row.iter().map(CellType::symbol).collect())
+        .collect();
+    let solution = problem
+        .iter()
+        .zip(bulbs)
+        .map(|(row, bulb_row)| {
+            row.iter()
+                .zip(bulb_row)
+                .map(|(cell, bulb)| if *bulb { "o".to_string() } else { cell.clone() })
+                .collect()
+        })
+        .collect();
+
+    Puzzle {
+        id: PuzzleId::Generated,
+        metadata: PuzzleMetadata {
+            puzzle_type: "akari".to_string(),
+            author: "akartui-rs generator".to_string(),
+            solver: String::new(),
+            source: "generated".to_string(),
+            info: format!("seed {}, {}% walls", seed, options.wall_percent),
+            size: PuzzleSize {
+                cols: board[0].len(),
+                rows: board.len(),
+                unit: 25,
+            },
+        },
+        problem,
+        solution: Some(solution),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: GeneratorOptions = GeneratorOptions {
+        rows: 6,
+        cols: 6,
+        wall_percent: 20,
+    };
+
+    #[test]
+    fn generated_puzzle_is_unique_and_repeatable() {
+        let puzzle = generate(OPTIONS, 7);
+        assert_eq!(solver::uniqueness(&puzzle.board()), Uniqueness::Unique);
+        assert_eq!(generate(OPTIONS, 7).problem, puzzle.problem);
+    }
+
+    #[test]
+    fn reports_progress_and_stops_when_cancelled() {
+        let progress = Progress::default();
+        assert!(generate_with_progress(OPTIONS, 7, &progress).is_some());
+        let (checked, clues) = progress.clues();
+        assert_eq!(checked, clues);
+
+        let progress = Progress::default();
+        progress.cancel();
+        assert!(generate_with_progress(OPTIONS, 7, &progress).is_none());
+    }
+}
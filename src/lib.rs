@@ -0,0 +1,23 @@
+//! The Akari (Light Up) rules engine behind akartui-rs, with no terminal code.
+//!
+//! - Load puzzles from archive JSON ([`game::Puzzle::from_json`]), the text
+//!   notation ([`notation`]), puzz.link URLs ([`pzpr`]) or the bundled archive
+//!   ([`archive::ArchiveIndex`]).
+//! - Play them with [`game::Game`]: [`game::Game::place`] applies a move,
+//!   [`game::Game::is_lit`] and `light_state` report the lighting, and
+//!   [`game::Game::validate`] lists broken rules.
+//! - Check any bulb layout with [`game::rule_violations`], solve boards with
+//!   [`solver`], explain deductions with [`logic`] and build new puzzles with
+//!   [`generator`].
+//!
+//! The `tui` feature, on by default, only adds the `akartui-rs` binary; build
+//! with `default-features = false` to depend on the engine alone.
+
+pub mod archive;
+pub mod error;
+pub mod game;
+pub mod generator;
+pub mod logic;
+pub mod notation;
+pub mod pzpr;
+pub mod solver;
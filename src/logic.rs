@@ -0,0 +1,632 @@
+use crate::game::{fmt_pos, grid_size, CellType, PlayerObject};
+use crate::solver::{Grid, Mark, State};
+
+// Human-style deductions on top of the solver's grid model. Unlike the solver,
+// every step here starts from what the player can see on the board, so each
+// one can be explained in a sentence.
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Technique {
+    // a clue already has all its bulbs, so its other sides stay empty
+    ClueSatisfied,
+    // a clue has exactly as many free sides as bulbs still missing
+    ClueNeedsAll,
+    // an unlit cell has a single place left that could light it
+    OnlyLightSource,
+    // a bulb here would leave some cell or clue impossible to satisfy
+    WouldDarken,
+    // only following a guess through to a contradiction helps
+    TrialAndError,
+    // the bulbs and flags on the board cannot lead to a solution
+    Mistake,
+}
+
+impl Technique {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Technique::ClueSatisfied => "Clue satisfied",
+            Technique::ClueNeedsAll => "Clue needs every side",
+            Technique::OnlyLightSource => "Single light source",
+            Technique::WouldDarken => "Blocked by lookahead",
+            Technique::TrialAndError => "Trial and error",
+            Technique::Mistake => "Mistake",
+        }
+    }
+}
+
+// More trial-and-error steps than this make a puzzle Expert.
+const HARD_TRIAL_LIMIT: usize = 5;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Hint {
+    pub technique: Technique,
+    /// What to place on `cells`; `None` when the hint points out a mistake.
+    pub object: Option<PlayerObject>,
+    pub cells: Vec<(usize, usize)>,
+    /// The clue or cell the reasoning starts from, if there is a single one.
+    pub source: Option<(usize, usize)>,
+    pub reason: String,
+}
+
+impl Hint {
+    fn place(
+        technique: Technique,
+        object: PlayerObject,
+        cells: Vec<(usize, usize)>,
+        source: (usize, usize),
+        reason: String,
+    ) -> Hint {
+        Hint {
+            technique,
+            object: Some(object),
+            cells,
+            source: Some(source),
+            reason,
+        }
+    }
+
+    fn mistake(cells: Vec<(usize, usize)>, source: Option<(usize, usize)>, reason: String) -> Hint {
+        Hint {
+            technique: Technique::Mistake,
+            object: None,
+            cells,
+            source,
+            reason,
+        }
+    }
+}
+
+/// Find the next deduction the player can make from their current placements,
+/// trying the simplest techniques first. Returns `None` once the board is
+/// solved, or if `objects` does not have the board's shape.
+pub fn next_hint(board: &[Vec<CellType>], objects: &[Vec<PlayerObject>]) -> Option<Hint> {
+    if grid_size(objects) != grid_size(board) {
+        return None;
+    }
+    let grid = Grid::new(board)?;
+    let state = match player_state(&grid, objects) {
+        Ok(state) => state,
+        Err(hint) => return Some(hint),
+    };
+
+    if let Some(hint) = find_mistake(&grid, &state) {
+        return Some(hint);
+    }
+    if (0..grid.cells.len()).all(|idx| state.lit[idx]) {
+        return None;
+    }
+
+    clue_satisfied(&grid, &state)
+        .or_else(|| clue_needs_all(&grid, &state))
+        .or_else(|| only_light_source(&grid, &state))
+        .or_else(|| would_darken(&grid, &state, 0))
+        .or_else(|| trial_and_error(&grid, &state))
+        .or_else(|| Some(solver_fallback(&grid, &state)))
+}
+
+/// Grade a board by what a human-style solve needs: the basic clue and
+/// lighting rules are Easy, one-bulb lookahead is Medium, a few trial-and-error
+/// steps are Hard and anything beyond that is Expert. Returns `None` if the
+/// board cannot be solved.
+pub fn rate_difficulty(board: &[Vec<CellType>]) -> Option<Difficulty> {
+    let grid = Grid::new(board)?;
+    let mut state = grid.initial_state();
+    let mut queue: Vec<usize> = (0..grid.cells.len()).collect();
+    let mut rating = Difficulty::Easy;
+    let mut trials = 0;
+    // cells before this had nothing for the lookahead last time it ran
+    let mut resume = 0;
+
+    loop {
+        // the solver's propagation applies exactly the basic rules
+        if grid.propagate(&mut state, &mut queue).is_err() {
+            return None;
+        }
+        if (0..grid.cells.len()).all(|idx| state.lit[idx]) {
+            return Some(rating);
+        }
+
+        // rescanning from the first cell after every step is what makes
+        // large boards slow, so pick up where the last scan left off
+        let step = would_darken(&grid, &state, resume)
+            .or_else(|| would_darken(&grid, &state, 0).filter(|_| resume > 0))
+            .or_else(|| trial_and_error(&grid, &state));
+        let Some(step) = step else {
+            // nothing short of a real search gets further
+            return Some(Difficulty::Expert);
+        };
+        match step.technique {
+            Technique::Mistake => return None,
+            Technique::TrialAndError => {
+                trials += 1;
+                if trials > HARD_TRIAL_LIMIT {
+                    return Some(Difficulty::Expert);
+                }
+                rating = rating.max(Difficulty::Hard);
+            }
+            _ => rating = rating.max(Difficulty::Medium),
+        }
+        if let Some(&(row, col)) = step.cells.first() {
+            resume = row * grid.cols + col;
+        }
+
+        for (row, col) in step.cells {
+            let idx = row * grid.cols + col;
+            if step.object == Some(PlayerObject::Lightbulb) {
+                if grid.place_bulb(&mut state, idx, &mut queue).is_err() {
+                    return None;
+                }
+            } else {
+                grid.block(&mut state, idx, &mut queue);
+            }
+        }
+    }
+}
+
+// Replay the player's bulbs and flags; flags count as "no bulb here".
+fn player_state(grid: &Grid, objects: &[Vec<PlayerObject>]) -> Result<State, Hint> {
+    let mut state = grid.initial_state();
+    let mut queue = Vec::new();
+    let placed = |object: PlayerObject| {
+        objects
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(move |(_, o)| **o == object)
+            .map(|(idx, _)| idx)
+    };
+
+    for idx in placed(PlayerObject::Lightbulb) {
+        if grid.place_bulb(&mut state, idx, &mut queue).is_err() {
+            let other = grid.sight[idx]
+                .iter()
+                .copied()
+                .find(|&v| state.marks[v] == Mark::Bulb)
+                .unwrap_or(idx);
+            return Err(Hint::mistake(
+                vec![grid.position(idx), grid.position(other)],
+                Some(grid.position(idx)),
+                format!(
+                    "The bulbs at {} and {} shine on each other",
+                    fmt_pos(grid.position(idx)),
+                    fmt_pos(grid.position(other))
+                ),
+            ));
+        }
+    }
+    for idx in placed(PlayerObject::Flag) {
+        grid.block(&mut state, idx, &mut queue);
+    }
+    Ok(state)
+}
+
+fn clue_counts(grid: &Grid, state: &State, idx: usize) -> (usize, Vec<usize>) {
+    let around = &grid.neighbours[idx];
+    let bulbs = around
+        .iter()
+        .filter(|&&v| state.marks[v] == Mark::Bulb)
+        .count();
+    let free = around
+        .iter()
+        .copied()
+        .filter(|&v| state.marks[v] == Mark::Unknown)
+        .collect();
+    (bulbs, free)
+}
+
+fn clues(grid: &Grid) -> impl Iterator<Item = (usize, usize)> + '_ {
+    grid.cells
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, cell)| match cell {
+            CellType::Target(n) => Some((idx, *n as usize)),
+            _ => None,
+        })
+}
+
+fn find_mistake(grid: &Grid, state: &State) -> Option<Hint> {
+    for (idx, n) in clues(grid) {
+        let (bulbs, free) = clue_counts(grid, state, idx);
+        let at = grid.position(idx);
+        if bulbs > n {
+            let cells = grid.neighbours[idx]
+                .iter()
+                .filter(|&&v| state.marks[v] == Mark::Bulb)
+                .map(|&v| grid.position(v))
+                .collect();
+            return Some(Hint::mistake(
+                cells,
+                Some(at),
+                format!("The {} at {} has too many bulbs", n, fmt_pos(at)),
+            ));
+        }
+        if bulbs + free.len() < n {
+            return Some(Hint::mistake(
+                Vec::new(),
+                Some(at),
+                format!(
+                    "The {} at {} can no longer get enough bulbs",
+                    n,
+                    fmt_pos(at)
+                ),
+            ));
+        }
+    }
+    for idx in 0..grid.cells.len() {
+        if !state.lit[idx] && grid.candidates(state, idx).next().is_none() {
+            let at = grid.position(idx);
+            return Some(Hint::mistake(
+                Vec::new(),
+                Some(at),
+                format!("The cell at {} can no longer be lit", fmt_pos(at)),
+            ));
+        }
+    }
+    None
+}
+
+fn clue_satisfied(grid: &Grid, state: &State) -> Option<Hint> {
+    clues(grid).find_map(|(idx, n)| {
+        let (bulbs, free) = clue_counts(grid, state, idx);
+        if free.is_empty() || bulbs != n {
+            return None;
+        }
+        let at = grid.position(idx);
+        let reason = if n == 0 {
+            format!("The 0 at {} allows no bulbs next to it", fmt_pos(at))
+        } else {
+            format!(
+                "The {} at {} already has its bulbs, so its other sides stay empty",
+                n,
+                fmt_pos(at)
+            )
+        };
+        Some(Hint::place(
+            Technique::ClueSatisfied,
+            PlayerObject::Flag,
+            free.iter().map(|&v| grid.position(v)).collect(),
+            at,
+            reason,
+        ))
+    })
+}
+
+fn clue_needs_all(grid: &Grid, state: &State) -> Option<Hint> {
+    clues(grid).find_map(|(idx, n)| {
+        let (bulbs, free) = clue_counts(grid, state, idx);
+        if free.is_empty() || bulbs + free.len() != n {
+            return None;
+        }
+        let at = grid.position(idx);
+        let reason = if free.len() == 4 {
+            format!("The {} at {} forces bulbs on all sides", n, fmt_pos(at))
+        } else {
+            format!(
+                "The {} at {} needs a bulb on each of its {} free sides",
+                n,
+                fmt_pos(at),
+                free.len()
+            )
+        };
+        Some(Hint::place(
+            Technique::ClueNeedsAll,
+            PlayerObject::Lightbulb,
+            free.iter().map(|&v| grid.position(v)).collect(),
+            at,
+            reason,
+        ))
+    })
+}
+
+fn only_light_source(grid: &Grid, state: &State) -> Option<Hint> {
+    (0..grid.cells.len()).find_map(|idx| {
+        if state.lit[idx] {
+            return None;
+        }
+        let mut candidates = grid.candidates(state, idx);
+        let (Some(only), None) = (candidates.next(), candidates.next()) else {
+            return None;
+        };
+        let at = grid.position(idx);
+        let reason = if only == idx {
+            format!(
+                "The cell at {} can only be lit by a bulb on itself",
+                fmt_pos(at)
+            )
+        } else {
+            format!(
+                "The cell at {} can only be lit from {}",
+                fmt_pos(at),
+                fmt_pos(grid.position(only))
+            )
+        };
+        Some(Hint::place(
+            Technique::OnlyLightSource,
+            PlayerObject::Lightbulb,
+            vec![grid.position(only)],
+            at,
+            reason,
+        ))
+    })
+}
+
+// Look one bulb ahead: would its light cut off a cell or starve a clue?
+// Only cells from `start` on are tried.
+fn would_darken(grid: &Grid, state: &State, start: usize) -> Option<Hint> {
+    let mut lights = vec![false; grid.cells.len()];
+    for idx in start..grid.cells.len() {
+        if state.marks[idx] != Mark::Unknown {
+            continue;
+        }
+        let lit_by = || std::iter::once(idx).chain(grid.sight[idx].iter().copied());
+        lit_by().for_each(|v| lights[v] = true);
+
+        let at = grid.position(idx);
+        let mut reason = None;
+        for v in lit_by() {
+            // unlit cells that might have relied on `v` to light them
+            for &u in &grid.sight[v] {
+                if !state.lit[u] && !lights[u] && grid.candidates(state, u).all(|c| lights[c]) {
+                    reason = Some((
+                        grid.position(u),
+                        format!(
+                            "A bulb at {} would leave the cell at {} with no way to be lit",
+                            fmt_pos(at),
+                            fmt_pos(grid.position(u))
+                        ),
+                    ));
+                    break;
+                }
+            }
+            // clues next to `v` lose it as a free side
+            for &k in &grid.neighbours[v] {
+                let CellType::Target(n) = grid.cells[k] else {
+                    continue;
+                };
+                let (bulbs, free) = clue_counts(grid, state, k);
+                let bulbs = bulbs + usize::from(grid.neighbours[k].contains(&idx));
+                let free = free.iter().filter(|&&f| !lights[f]).count();
+                if bulbs > n as usize || bulbs + free < n as usize {
+                    reason = Some((
+                        grid.position(k),
+                        format!(
+                            "A bulb at {} would leave the {} at {} unsatisfiable",
+                            fmt_pos(at),
+                            n,
+                            fmt_pos(grid.position(k))
+                        ),
+                    ));
+                    break;
+                }
+            }
+            if reason.is_some() {
+                break;
+            }
+        }
+
+        lit_by().for_each(|v| lights[v] = false);
+        if let Some((source, reason)) = reason {
+            return Some(Hint::place(
+                Technique::WouldDarken,
+                PlayerObject::Flag,
+                vec![at],
+                source,
+                reason,
+            ));
+        }
+    }
+    None
+}
+
+// Follow each choice through full propagation and report the first one that breaks.
+fn trial_and_error(grid: &Grid, state: &State) -> Option<Hint> {
+    let mut settled = state.clone();
+    let mut queue: Vec<usize> = (0..grid.cells.len()).collect();
+    if grid.propagate(&mut settled, &mut queue).is_err() {
+        return Some(Hint::mistake(
+            Vec::new(),
+            None,
+            "The bulbs and flags placed so far cannot lead to a solution".to_string(),
+        ));
+    }
+
+    for idx in 0..grid.cells.len() {
+        if state.marks[idx] != Mark::Unknown || settled.marks[idx] != Mark::Unknown {
+            continue;
+        }
+        let at = grid.position(idx);
+
+        let mut trial = settled.clone();
+        let bulb_fails = grid.place_bulb(&mut trial, idx, &mut queue).is_err()
+            || grid.propagate(&mut trial, &mut queue).is_err();
+        queue.clear();
+        if bulb_fails {
+            return Some(Hint::place(
+                Technique::TrialAndError,
+                PlayerObject::Flag,
+                vec![at],
+                at,
+                format!(
+                    "Assuming a bulb at {} leads to a contradiction",
+                    fmt_pos(at)
+                ),
+            ));
+        }
+
+        let mut trial = settled.clone();
+        grid.block(&mut trial, idx, &mut queue);
+        let block_fails = grid.propagate(&mut trial, &mut queue).is_err();
+        queue.clear();
+        if block_fails {
+            return Some(Hint::place(
+                Technique::TrialAndError,
+                PlayerObject::Lightbulb,
+                vec![at],
+                at,
+                format!(
+                    "Leaving the cell at {} without a bulb leads to a contradiction",
+                    fmt_pos(at)
+                ),
+            ));
+        }
+    }
+    None
+}
+
+// When no single step works, point at a bulb from the solver's answer.
+fn solver_fallback(grid: &Grid, state: &State) -> Hint {
+    let solved = grid.solutions_from(state.clone(), 1);
+    let Some(solution) = solved.first() else {
+        return Hint::mistake(
+            Vec::new(),
+            None,
+            "The bulbs and flags placed so far cannot lead to a solution".to_string(),
+        );
+    };
+    let idx = (0..grid.cells.len())
+        .find(|&idx| state.marks[idx] == Mark::Unknown && solution.marks[idx] == Mark::Bulb)
+        .unwrap_or(0);
+    let at = grid.position(idx);
+    Hint::place(
+        Technique::TrialAndError,
+        PlayerObject::Lightbulb,
+        vec![at],
+        at,
+        format!("No simple deduction left; try a bulb at {}", fmt_pos(at)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation;
+
+    fn board(text: &str) -> Vec<Vec<CellType>> {
+        notation::parse(text).unwrap().puzzle.board()
+    }
+
+    // Walls and clues as the game stores them, with nothing placed yet.
+    fn untouched(board: &[Vec<CellType>]) -> Vec<Vec<PlayerObject>> {
+        board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        CellType::Empty => PlayerObject::Empty,
+                        _ => PlayerObject::IsWall,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // needs a guess: nothing short of trial and error gets past the start
+    const HARD: &str = "- - - x\n- 1 - -\n- - - x\n- 0 - -";
+
+    fn first_hint(text: &str) -> Hint {
+        let board = board(text);
+        next_hint(&board, &untouched(&board)).unwrap()
+    }
+
+    #[test]
+    fn spots_a_satisfied_clue() {
+        let hint = first_hint("0 -");
+        assert_eq!(hint.technique, Technique::ClueSatisfied);
+        assert_eq!(hint.object, Some(PlayerObject::Flag));
+        assert_eq!(hint.cells, [(0, 1)]);
+        assert_eq!(hint.source, Some((0, 0)));
+    }
+
+    #[test]
+    fn spots_a_clue_needing_every_side() {
+        let hint = first_hint("- 2 -");
+        assert_eq!(hint.technique, Technique::ClueNeedsAll);
+        assert_eq!(hint.object, Some(PlayerObject::Lightbulb));
+        assert_eq!(hint.cells, [(0, 0), (0, 2)]);
+        assert_eq!(hint.source, Some((0, 1)));
+    }
+
+    #[test]
+    fn spots_the_only_light_source() {
+        let hint = first_hint("- x\nx x");
+        assert_eq!(hint.technique, Technique::OnlyLightSource);
+        assert_eq!(hint.object, Some(PlayerObject::Lightbulb));
+        assert_eq!(hint.cells, [(0, 0)]);
+    }
+
+    #[test]
+    fn looks_one_bulb_ahead() {
+        // a bulb in the corner would light both free sides of the 1
+        let hint = first_hint("- - -\n- 1 x\nx x x");
+        assert_eq!(hint.technique, Technique::WouldDarken);
+        assert_eq!(hint.object, Some(PlayerObject::Flag));
+        assert_eq!(hint.cells, [(0, 0)]);
+        assert_eq!(hint.source, Some((1, 1)));
+    }
+
+    #[test]
+    fn falls_back_to_trial_and_error() {
+        let board = board(HARD);
+        let grid = Grid::new(&board).unwrap();
+        let mut state = grid.initial_state();
+        let mut queue: Vec<usize> = (0..grid.cells.len()).collect();
+        assert!(grid.propagate(&mut state, &mut queue).is_ok());
+        assert!(would_darken(&grid, &state, 0).is_none());
+
+        let hint = trial_and_error(&grid, &state).unwrap();
+        assert_eq!(hint.technique, Technique::TrialAndError);
+        assert_eq!(hint.object, Some(PlayerObject::Flag));
+        assert_eq!(hint.cells, [(0, 0)]);
+    }
+
+    #[test]
+    fn points_out_mistakes() {
+        let board = board("- - -");
+        let mut objects = untouched(&board);
+        objects[0][0] = PlayerObject::Lightbulb;
+        objects[0][2] = PlayerObject::Lightbulb;
+        let hint = next_hint(&board, &objects).unwrap();
+        assert_eq!(hint.technique, Technique::Mistake);
+        assert_eq!(hint.object, None);
+        assert_eq!(hint.cells, [(0, 2), (0, 0)]);
+
+        let board = self::board("- 1 -");
+        let mut objects = untouched(&board);
+        objects[0][0] = PlayerObject::Lightbulb;
+        objects[0][2] = PlayerObject::Lightbulb;
+        let hint = next_hint(&board, &objects).unwrap();
+        assert_eq!(hint.technique, Technique::Mistake);
+        assert_eq!(hint.cells, [(0, 0), (0, 2)]);
+        assert_eq!(hint.source, Some((0, 1)));
+    }
+
+    #[test]
+    fn rates_difficulty() {
+        assert_eq!(rate_difficulty(&board("- 2 -")), Some(Difficulty::Easy));
+        assert_eq!(
+            rate_difficulty(&board("- - -\n- 1 x\nx x x")),
+            Some(Difficulty::Medium)
+        );
+        assert_eq!(rate_difficulty(&board(HARD)), Some(Difficulty::Hard));
+        assert_eq!(rate_difficulty(&board("1 x")), None);
+    }
+}
@@ -1,25 +1,174 @@
-use crossterm::event::{self, Event};
+use crossterm::event::{self, DisableFocusChange, EnableFocusChange, Event};
+use crossterm::execute;
 //use ratatui::DefaultTerminal;
+use std::env;
 use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
 mod app;
-mod game;
+mod audit;
+mod cli;
+mod save;
+mod stats;
 mod ui;
+use akartui_rs::archive::{self, ArchiveSource};
 use app::App;
 use ui::ui;
 
+// How long to wait for input before redrawing, so the clock keeps ticking.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+const USAGE: &str =
+    "usage: akartui-rs [--archive <dir>] [--library <dir>] [<file> | <puzz.link URL>]
+       akartui-rs solve <puzzle>
+       akartui-rs validate <puzzle> --solution <file>
+       akartui-rs render <puzzle>
+       akartui-rs info <puzzle>
+       akartui-rs audit
+<puzzle> is an archive number, a library name, a .txt or .json file, or a puzz.link URL";
+
+enum Command {
+    /// Start the TUI, on this puzzle file or URL if one is given.
+    Play(Option<String>),
+    /// Check the archive instead of starting the game.
+    Audit,
+    Headless(cli::Command),
+}
+
+struct Args {
+    archive: Option<PathBuf>,
+    library: Option<PathBuf>,
+    command: Command,
+}
+
+// `--archive <dir>`, `--library <dir>` and `--solution <file>` (or
+// `--name=<value>`), plus a subcommand or a puzzle to open straight away;
+// anything else is a usage error.
+fn parse_args() -> Result<Args, String> {
+    let mut archive = None;
+    let mut library = None;
+    let mut solution = None;
+    let mut words = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=').filter(|_| arg.starts_with("--")) {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        match flag.as_str() {
+            "--archive" | "--library" | "--solution" => {
+                let value = inline
+                    .or_else(|| args.next())
+                    .ok_or(format!("{} needs a value", flag))?;
+                match flag.as_str() {
+                    "--archive" => archive = Some(PathBuf::from(value)),
+                    "--library" => library = Some(PathBuf::from(value)),
+                    _ => solution = Some(value),
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if !flag.starts_with('-') => words.push(flag),
+            _ => return Err(format!("unexpected argument: {}", flag)),
+        }
+    }
+
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let command = match words.as_slice() {
+        [] => Command::Play(None),
+        ["audit"] => Command::Audit,
+        ["solve", puzzle] => Command::Headless(cli::Command::Solve(puzzle.to_string())),
+        ["render", puzzle] => Command::Headless(cli::Command::Render(puzzle.to_string())),
+        ["info", puzzle] => Command::Headless(cli::Command::Info(puzzle.to_string())),
+        ["validate", puzzle] => Command::Headless(cli::Command::Validate {
+            puzzle: puzzle.to_string(),
+            solution: solution.take().ok_or("validate needs --solution <file>")?,
+        }),
+        [command @ ("solve" | "render" | "info" | "validate")] => {
+            return Err(format!("{} needs a puzzle", command))
+        }
+        [puzzle] => Command::Play(Some(puzzle.to_string())),
+        [_, extra, ..] => return Err(format!("unexpected argument: {}", extra)),
+    };
+    if solution.is_some() {
+        return Err("--solution only goes with validate".to_string());
+    }
+    Ok(Args {
+        archive,
+        library,
+        command,
+    })
+}
+
 fn main() -> io::Result<()> {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let source = match ArchiveSource::resolve(args.archive) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let puzzle = match args.command {
+        Command::Play(puzzle) => puzzle,
+        Command::Audit => {
+            let clean = audit::run(&source);
+            process::exit(if clean { 0 } else { 1 });
+        }
+        Command::Headless(command) => process::exit(cli::run(
+            command,
+            source,
+            archive::resolve_library(args.library),
+        )),
+    };
+
+    let imported = match puzzle.as_deref().map(cli::open_puzzle).transpose() {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let mut app = App::new(source, archive::resolve_library(args.library)); // Using new() with explicit initialization
+    if let Some((puzzle, bulbs)) = imported {
+        if let Err(e) = app.start_imported_game(puzzle, &bulbs) {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    }
+
     let mut terminal = ratatui::init();
-    let mut app = App::new(); // Using new() with explicit initialization
+    // Report focus changes so the game can pause when the window is left
+    execute!(io::stdout(), EnableFocusChange)?;
 
     // Main loop moved to main.rs
     while !app.should_quit() {
+        app.tick();
         terminal.draw(|frame| ui(frame, &mut app))?;
-        if let Event::Key(key) = event::read()? {
-            app.handle_event(key);
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
+        match event::read()? {
+            Event::Key(key) => app.handle_event(key),
+            Event::FocusLost => app.pause(),
+            _ => {}
         }
     }
 
+    execute!(io::stdout(), DisableFocusChange)?;
     ratatui::restore();
     Ok(())
 }
@@ -0,0 +1,196 @@
+use crate::error::{PuzzleError, PuzzleErrorKind};
+use crate::game::{CellType, PlayerObject, Puzzle, PuzzleId, PuzzleMetadata, PuzzleSize};
+
+// The plain-text grid used in the README and the help screen: `0`-`4` for
+// clues, `x` for walls, `-` for empty cells and `o` for bulbs, one row per
+// line. Spaces between cells and blank lines are ignored.
+
+/// A puzzle read from text, with any bulbs that were drawn on it.
+pub struct TextGrid {
+    pub puzzle: Puzzle,
+    pub bulbs: Vec<(usize, usize)>,
+}
+
+/// Parse a grid; errors name the line and column they were found at.
+pub fn parse(text: &str) -> Result<TextGrid, PuzzleError> {
+    let mut problem: Vec<Vec<String>> = Vec::new();
+    let mut bulbs = Vec::new();
+    // line number of the first row, to point at when the widths disagree
+    let mut first_line = 0;
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut row = Vec::new();
+        let mut end = 0;
+        for (col_index, c) in line.chars().enumerate() {
+            let column = col_index + 1;
+            let symbol = match c {
+                ' ' | '\t' | '\r' => continue,
+                '0'..='4' | 'x' | '-' => c.to_string(),
+                'o' => {
+                    bulbs.push((problem.len(), row.len()));
+                    "-".to_string()
+                }
+                _ => {
+                    return Err(syntax(
+                        line_number,
+                        column,
+                        format!("unknown symbol '{}'", c),
+                    ))
+                }
+            };
+            if let Some(first) = problem.first() {
+                if row.len() == first.len() {
+                    return Err(syntax(
+                        line_number,
+                        column,
+                        format!(
+                            "row is longer than the {} cells on line {}",
+                            first.len(),
+                            first_line
+                        ),
+                    ));
+                }
+            }
+            row.push(symbol);
+            end = column;
+        }
+        if row.is_empty() {
+            continue;
+        }
+        match problem.first() {
+            None => first_line = line_number,
+            Some(first) if row.len() < first.len() => {
+                return Err(syntax(
+                    line_number,
+                    end + 1,
+                    format!(
+                        "row has {} cells, but line {} has {}",
+                        row.len(),
+                        first_line,
+                        first.len()
+                    ),
+                ));
+            }
+            Some(_) => {}
+        }
+        problem.push(row);
+    }
+
+    let Some(first) = problem.first() else {
+        return Err(PuzzleErrorKind::EmptyGrid.into());
+    };
+    let cols = first.len();
+    Ok(TextGrid {
+        puzzle: Puzzle {
+            id: PuzzleId::Imported,
+            metadata: PuzzleMetadata {
+                puzzle_type: "akari".to_string(),
+                author: String::new(),
+                solver: String::new(),
+                source: "text".to_string(),
+                info: String::new(),
+                size: PuzzleSize {
+                    cols,
+                    rows: problem.len(),
+                    unit: 25,
+                },
+            },
+            problem,
+            solution: None,
+        },
+        bulbs,
+    })
+}
+
+fn syntax(line: usize, column: usize, message: String) -> PuzzleError {
+    PuzzleErrorKind::Syntax {
+        line,
+        column,
+        message,
+    }
+    .into()
+}
+
+/// Print `board` in the notation, marking every bulb in `objects` (which may
+/// be empty to print just the puzzle).
+pub fn to_text(board: &[Vec<CellType>], objects: &[Vec<PlayerObject>]) -> String {
+    let mut text = String::new();
+    for (i, row) in board.iter().enumerate() {
+        let cells: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(j, cell)| {
+                let object = objects.get(i).and_then(|row| row.get(j));
+                if object == Some(&PlayerObject::Lightbulb) {
+                    "o".to_string()
+                } else {
+                    cell.symbol()
+                }
+            })
+            .collect();
+        text.push_str(&cells.join(" "));
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(text: &str) -> (usize, usize, String) {
+        match parse(text).map(|_| ()).unwrap_err().kind {
+            PuzzleErrorKind::Syntax {
+                line,
+                column,
+                message,
+            } => (line, column, message),
+            kind => panic!("expected a syntax error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn reads_grid_and_bulbs() {
+        let grid = parse("1 - o\n\nx - 4\n").unwrap();
+        assert_eq!(grid.puzzle.metadata.size.rows, 2);
+        assert_eq!(grid.puzzle.metadata.size.cols, 3);
+        assert_eq!(grid.bulbs, vec![(0, 2)]);
+        assert_eq!(grid.puzzle.problem[0], ["1", "-", "-"]);
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let text = "1 - o\nx - 4\n";
+        let grid = parse(text).unwrap();
+        let mut objects = vec![vec![PlayerObject::Empty; 3]; 2];
+        objects[0][2] = PlayerObject::Lightbulb;
+        assert_eq!(to_text(&grid.puzzle.board(), &objects), text);
+    }
+
+    #[test]
+    fn unknown_symbol_position() {
+        let (line, column, message) = position("- - -\n- ? -");
+        assert_eq!((line, column), (2, 3));
+        assert_eq!(message, "unknown symbol '?'");
+    }
+
+    #[test]
+    fn long_row_position() {
+        let (line, column, _) = position("- -\n\n- - x");
+        assert_eq!((line, column), (3, 5));
+    }
+
+    #[test]
+    fn short_row_position() {
+        let (line, column, message) = position("- - -\n- -");
+        assert_eq!((line, column), (2, 4));
+        assert_eq!(message, "row has 2 cells, but line 1 has 3");
+    }
+
+    #[test]
+    fn empty_text() {
+        let err = parse("\n \n").map(|_| ()).unwrap_err();
+        assert!(matches!(err.kind, PuzzleErrorKind::EmptyGrid));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::game::{CellType, Puzzle, PuzzleId, PuzzleMetadata, PuzzleSize};
+
+// puzz.link / pzprjs URLs for Akari: `https://puzz.link/p?lightup/W/H/BODY`.
+// The body lists the cells row-major: `0`-`4` is a clue, `5`-`9` a clue
+// followed by one empty cell, `a`-`e` a clue followed by two, `.` a wall
+// without a number, and `g`-`z` a run of 1 to 20 empty cells.
+
+const URL_BASE: &str = "https://puzz.link/p?lightup";
+
+/// The widest or tallest board a URL may ask for; the archive's largest
+/// puzzle is 100x100.
+pub const MAX_SIZE: usize = 100;
+
+/// Encode `board` as a puzz.link URL.
+pub fn to_url(board: &[Vec<CellType>]) -> String {
+    let rows = board.len();
+    let cols = board.first().map_or(0, |row| row.len());
+    let cells: Vec<CellType> = board.iter().flatten().copied().collect();
+    let empty = |idx: usize| cells.get(idx).is_none_or(|cell| *cell == CellType::Empty);
+
+    let mut body = String::new();
+    let mut run = 0;
+    let mut idx = 0;
+    while idx < cells.len() {
+        let symbol = match cells[idx] {
+            CellType::Empty => {
+                run += 1;
+                None
+            }
+            CellType::Wall => Some('.'),
+            // fold up to two following empty cells into the clue
+            CellType::Target(n) if !empty(idx + 1) => Some(digit(n as u32)),
+            CellType::Target(n) if !empty(idx + 2) => {
+                idx += 1;
+                Some(digit(5 + n as u32))
+            }
+            CellType::Target(n) => {
+                idx += 2;
+                Some(digit(10 + n as u32))
+            }
+        };
+        if run > 0 && (symbol.is_some() || run == 20) {
+            body.push(digit(15 + run));
+            run = 0;
+        }
+        if let Some(symbol) = symbol {
+            body.push(symbol);
+        }
+        idx += 1;
+    }
+    if run > 0 {
+        body.push(digit(15 + run));
+    }
+
+    format!("{}/{}/{}/{}", URL_BASE, cols, rows, body)
+}
+
+fn digit(value: u32) -> char {
+    char::from_digit(value, 36).unwrap_or('?')
+}
+
+/// Decode a puzz.link or pzv.jp URL, or just its `lightup/W/H/BODY` part.
+pub fn from_url(url: &str) -> Result<Puzzle, String> {
+    let query = url.trim().rsplit('?').next().unwrap_or_default();
+    let mut parts = query.split('/');
+    match parts.next() {
+        Some("lightup" | "akari") => {}
+        _ => return Err("not a lightup URL".to_string()),
+    }
+    // pzprjs may put flags before the size
+    let mut parts = parts.skip_while(|part| part.parse::<usize>().is_err());
+    let size = |part: Option<&str>, name: &str| {
+        part.and_then(|part| part.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("missing or bad {} in URL", name))
+            .and_then(|n| match n {
+                n if n > MAX_SIZE => Err(format!(
+                    "{} {} is larger than the limit of {}",
+                    name, n, MAX_SIZE
+                )),
+                n => Ok(n),
+            })
+    };
+    let cols = size(parts.next(), "width")?;
+    let rows = size(parts.next(), "height")?;
+    let body = parts.next().unwrap_or_default();
+
+    let mut cells = vec![CellType::Empty; rows * cols];
+    let mut idx = 0;
+    for (pos, c) in body.chars().enumerate() {
+        if idx >= cells.len() {
+            break;
+        }
+        let value = c
+            .to_digit(36)
+            .filter(|_| c.is_ascii_digit() || c.is_ascii_lowercase());
+        match (c, value) {
+            ('.', _) => {
+                cells[idx] = CellType::Wall;
+                idx += 1;
+            }
+            (_, Some(value @ 0..=14)) => {
+                cells[idx] = CellType::Target((value % 5) as u8);
+                idx += 1 + value as usize / 5;
+            }
+            (_, Some(value @ 16..=35)) => idx += value as usize - 15,
+            _ => {
+                return Err(format!(
+                    "unexpected '{}' at position {} of the URL body",
+                    c, pos
+                ))
+            }
+        }
+    }
+
+    let problem = cells
+        .chunks(cols)
+        .map(|row| row.iter().map(CellType::symbol).collect())
+        .collect();
+    Ok(Puzzle {
+        id: PuzzleId::Imported,
+        metadata: PuzzleMetadata {
+            puzzle_type: "akari".to_string(),
+            author: String::new(),
+            solver: String::new(),
+            source: "puzz.link".to_string(),
+            info: format!("{}/{}/{}/{}", URL_BASE, cols, rows, body),
+            size: PuzzleSize {
+                cols,
+                rows,
+                unit: 25,
+            },
+        },
+        problem,
+        solution: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The 4x3 board below, as puzz.link encodes it
+    const URL: &str = "https://puzz.link/p?lightup/4/3/h1.gbj";
+
+    fn board() -> Vec<Vec<CellType>> {
+        crate::notation::parse("- - 1 x\n- 1 - -\n- - - -")
+            .unwrap()
+            .puzzle
+            .board()
+    }
+
+    #[test]
+    fn decodes_known_url() {
+        let puzzle = from_url(URL).unwrap();
+        assert_eq!(puzzle.metadata.size.cols, 4);
+        assert_eq!(puzzle.metadata.size.rows, 3);
+        assert_eq!(puzzle.board(), board());
+    }
+
+    #[test]
+    fn encodes_known_url() {
+        assert_eq!(to_url(&board()), URL);
+    }
+
+    #[test]
+    fn accepts_bare_query_and_pzv_name() {
+        let puzzle = from_url("akari/4/3/h1.gbj").unwrap();
+        assert_eq!(puzzle.board(), board());
+    }
+
+    #[test]
+    fn short_body_leaves_the_rest_empty() {
+        let puzzle = from_url("lightup/3/2/.").unwrap();
+        let board = puzzle.board();
+        assert_eq!(board[0][0], CellType::Wall);
+        assert!(board
+            .iter()
+            .flatten()
+            .skip(1)
+            .all(|cell| *cell == CellType::Empty));
+    }
+
+    #[test]
+    fn rejects_oversized_board() {
+        let err = from_url("lightup/100000/100000/").unwrap_err();
+        assert!(err.contains("larger than the limit"), "{}", err);
+        assert!(from_url("lightup/100/101/").is_err());
+        assert!(from_url("lightup/100/100/").is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(from_url("https://puzz.link/p?nurikabe/3/3/g").is_err());
+        assert!(from_url("lightup/0/3/").is_err());
+        assert!(from_url("lightup/3/3/A").is_err());
+    }
+}
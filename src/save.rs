@@ -0,0 +1,207 @@
+use akartui_rs::archive;
+use akartui_rs::game::{AssistLog, CellType, Game, History, PlayerObject, PuzzleId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// In-progress games, one JSON file per puzzle under `saves/` in the data
+// directory.
+
+fn save_dir() -> PathBuf {
+    archive::data_dir().join("saves")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub puzzle_id: PuzzleId,
+    pub player_objects: Vec<Vec<PlayerObject>>,
+    pub cursor_position: (usize, usize),
+    pub history: History,
+    #[serde(default)]
+    pub undos: u32,
+    #[serde(default)]
+    pub assists: AssistLog,
+    pub elapsed_secs: u64,
+    /// Unix time of the save, so "Continue" can pick the latest one.
+    pub saved_at: u64,
+}
+
+impl SavedGame {
+    /// Snapshot `game`, or `None` if it cannot be opened again later.
+    pub fn capture(game: &Game, elapsed: Duration) -> Option<SavedGame> {
+        let puzzle_id = game.puzzle.as_ref()?.id.clone();
+        if !puzzle_id.is_tracked() {
+            return None;
+        }
+        Some(SavedGame {
+            puzzle_id,
+            player_objects: game.player_objects.clone(),
+            cursor_position: game.cursor_position,
+            history: game.history.clone(),
+            undos: game.undos,
+            assists: game.assists,
+            elapsed_secs: elapsed.as_secs(),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+        })
+    }
+
+    /// Put the saved progress back onto `game`, which must already hold the
+    /// same puzzle. Returns false if the save does not fit the board; a
+    /// history that does not is dropped, keeping the placements.
+    pub fn restore(self, game: &mut Game) -> bool {
+        let fits = self.player_objects.len() == game.board.len()
+            && self
+                .player_objects
+                .iter()
+                .zip(&game.board)
+                .all(|(saved, row)| {
+                    saved.len() == row.len()
+                        && saved.iter().zip(row).all(|(object, cell)| {
+                            (*object == PlayerObject::IsWall) == (*cell != CellType::Empty)
+                        })
+                });
+        let (row, col) = self.cursor_position;
+        if !fits || row >= game.board.len() || col >= game.board[0].len() {
+            return false;
+        }
+        let open = |(row, col): (usize, usize)| {
+            game.board.get(row).and_then(|cells| cells.get(col)) == Some(&CellType::Empty)
+        };
+        let replayable = self.history.done <= self.history.moves.len()
+            && self.history.moves.iter().all(|change| {
+                open(change.position)
+                    && change.before != PlayerObject::IsWall
+                    && change.after != PlayerObject::IsWall
+            });
+
+        game.player_objects = self.player_objects;
+        game.cursor_position = self.cursor_position;
+        game.history = if replayable {
+            self.history
+        } else {
+            History::default()
+        };
+        game.undos = self.undos;
+        game.assists = self.assists;
+        game.update();
+        true
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs(self.elapsed_secs)
+    }
+}
+
+fn save_path(puzzle_id: &PuzzleId) -> PathBuf {
+    save_dir().join(format!("{}.json", puzzle_id.key()))
+}
+
+pub fn write(saved: &SavedGame) -> io::Result<()> {
+    let json = serde_json::to_string(saved)?;
+    fs::create_dir_all(save_dir())?;
+    fs::write(save_path(&saved.puzzle_id), json)
+}
+
+pub fn load(puzzle_id: &PuzzleId) -> Option<SavedGame> {
+    let text = fs::read_to_string(save_path(puzzle_id)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn remove(puzzle_id: &PuzzleId) {
+    let _ = fs::remove_file(save_path(puzzle_id));
+}
+
+/// IDs of every puzzle with a save file.
+pub fn saved_ids() -> BTreeSet<PuzzleId> {
+    let Ok(entries) = fs::read_dir(save_dir()) else {
+        return BTreeSet::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            PuzzleId::from_key(name.to_str()?.strip_suffix(".json")?)
+        })
+        .collect()
+}
+
+/// The most recently saved game, if any.
+pub fn latest() -> Option<SavedGame> {
+    saved_ids()
+        .iter()
+        .filter_map(load)
+        .max_by_key(|saved| saved.saved_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use akartui_rs::game::Move;
+    use akartui_rs::notation;
+
+    fn game() -> Game {
+        let mut puzzle = notation::parse("- 1 -\nx - -").unwrap().puzzle;
+        puzzle.id = PuzzleId::Archive(1);
+        let mut game = Game::new();
+        game.load_puzzle(puzzle).unwrap();
+        game
+    }
+
+    #[test]
+    fn round_trips_progress() {
+        let mut played = game();
+        played.place((0, 0), PlayerObject::Lightbulb);
+        played.place((1, 2), PlayerObject::Flag);
+        played.undo();
+        let saved = SavedGame::capture(&played, Duration::from_secs(42)).unwrap();
+        let json = serde_json::to_string(&saved).unwrap();
+
+        let mut restored = game();
+        let saved: SavedGame = serde_json::from_str(&json).unwrap();
+        assert!(saved.restore(&mut restored));
+        assert_eq!(restored.player_objects, played.player_objects);
+        assert_eq!(restored.history.done, 1);
+        assert_eq!(restored.history.moves.len(), 2);
+        assert!(restored.light_state == played.light_state);
+        restored.redo();
+        assert_eq!(restored.player_objects[1][2], PlayerObject::Flag);
+    }
+
+    #[test]
+    fn rejects_a_save_for_other_walls() {
+        let mut saved = SavedGame::capture(&game(), Duration::ZERO).unwrap();
+        saved.player_objects[1][0] = PlayerObject::Empty;
+        assert!(!saved.restore(&mut game()));
+
+        let mut saved = SavedGame::capture(&game(), Duration::ZERO).unwrap();
+        saved.player_objects[0][0] = PlayerObject::IsWall;
+        assert!(!saved.restore(&mut game()));
+    }
+
+    #[test]
+    fn drops_a_history_that_does_not_fit() {
+        let mut played = game();
+        played.place((0, 0), PlayerObject::Lightbulb);
+        let mut saved = SavedGame::capture(&played, Duration::ZERO).unwrap();
+        saved.history.done = 5;
+        let mut restored = game();
+        assert!(saved.restore(&mut restored));
+        assert_eq!(restored.player_objects[0][0], PlayerObject::Lightbulb);
+        assert!(restored.history.moves.is_empty());
+
+        let mut saved = SavedGame::capture(&played, Duration::ZERO).unwrap();
+        saved.history.moves.push(Move {
+            position: (7, 7),
+            before: PlayerObject::Empty,
+            after: PlayerObject::Lightbulb,
+        });
+        let mut restored = game();
+        assert!(saved.restore(&mut restored));
+        assert!(restored.history.moves.is_empty());
+    }
+}
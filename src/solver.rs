@@ -0,0 +1,396 @@
+use crate::game::{grid_size, CellType};
+
+// Akari solver: constraint propagation over the clue and lighting rules,
+// probing each open cell for contradictions, and backtracking on the unlit
+// cell with the fewest candidates once neither makes progress.
+
+#[derive(PartialEq, Clone, Copy)]
+pub(crate) enum Mark {
+    Unknown,
+    Bulb,
+    Blocked,
+}
+
+#[derive(Clone)]
+pub(crate) struct State {
+    pub(crate) marks: Vec<Mark>,
+    pub(crate) lit: Vec<bool>,
+}
+
+pub(crate) struct Contradiction;
+
+// The board flattened row-major, with the lines of sight precomputed.
+pub(crate) struct Grid {
+    pub(crate) cols: usize,
+    pub(crate) cells: Vec<CellType>,
+    // every empty cell a bulb at this index would light (excluding itself)
+    pub(crate) sight: Vec<Vec<usize>>,
+    // for clues: adjacent empty cells; for empty cells: adjacent clues
+    pub(crate) neighbours: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    // `None` for a ragged board, which has no cells to line up.
+    pub(crate) fn new(board: &[Vec<CellType>]) -> Option<Grid> {
+        let (rows, cols) = grid_size(board)?;
+        let cells: Vec<CellType> = board.iter().flatten().copied().collect();
+        let mut sight = vec![Vec::new(); rows * cols];
+        let mut neighbours = vec![Vec::new(); rows * cols];
+
+        for i in 0..rows {
+            for j in 0..cols {
+                let idx = i * cols + j;
+                match cells[idx] {
+                    CellType::Empty => {
+                        for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                            let (mut ni, mut nj) = (i as isize + di, j as isize + dj);
+                            while ni >= 0 && ni < rows as isize && nj >= 0 && nj < cols as isize {
+                                let nidx = ni as usize * cols + nj as usize;
+                                if cells[nidx] != CellType::Empty {
+                                    break;
+                                }
+                                sight[idx].push(nidx);
+                                ni += di;
+                                nj += dj;
+                            }
+                        }
+                    }
+                    CellType::Target(_) => {
+                        for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                            let ni = i as isize + di;
+                            let nj = j as isize + dj;
+                            if ni >= 0 && ni < rows as isize && nj >= 0 && nj < cols as isize {
+                                let nidx = ni as usize * cols + nj as usize;
+                                if cells[nidx] == CellType::Empty {
+                                    neighbours[idx].push(nidx);
+                                    neighbours[nidx].push(idx);
+                                }
+                            }
+                        }
+                    }
+                    CellType::Wall => {}
+                }
+            }
+        }
+
+        Some(Grid {
+            cols,
+            cells,
+            sight,
+            neighbours,
+        })
+    }
+
+    pub(crate) fn initial_state(&self) -> State {
+        let marks = self
+            .cells
+            .iter()
+            .map(|cell| match cell {
+                CellType::Empty => Mark::Unknown,
+                _ => Mark::Blocked,
+            })
+            .collect();
+        let lit = self
+            .cells
+            .iter()
+            .map(|cell| *cell != CellType::Empty)
+            .collect();
+        State { marks, lit }
+    }
+
+    // Queue everything whose deductions may change after `idx` is decided.
+    fn touch(&self, idx: usize, queue: &mut Vec<usize>) {
+        queue.push(idx);
+        queue.extend_from_slice(&self.neighbours[idx]);
+        queue.extend_from_slice(&self.sight[idx]);
+    }
+
+    pub(crate) fn block(&self, state: &mut State, idx: usize, queue: &mut Vec<usize>) {
+        if state.marks[idx] == Mark::Unknown {
+            state.marks[idx] = Mark::Blocked;
+            self.touch(idx, queue);
+        }
+    }
+
+    pub(crate) fn place_bulb(
+        &self,
+        state: &mut State,
+        idx: usize,
+        queue: &mut Vec<usize>,
+    ) -> Result<(), Contradiction> {
+        if state.marks[idx] != Mark::Unknown || state.lit[idx] {
+            return Err(Contradiction);
+        }
+        state.marks[idx] = Mark::Bulb;
+        state.lit[idx] = true;
+        self.touch(idx, queue);
+        for &v in &self.sight[idx] {
+            if state.marks[v] == Mark::Bulb {
+                return Err(Contradiction);
+            }
+            state.lit[v] = true;
+            self.block(state, v, queue);
+        }
+        Ok(())
+    }
+
+    // Apply forced moves for every queued cell until the queue drains.
+    pub(crate) fn propagate(
+        &self,
+        state: &mut State,
+        queue: &mut Vec<usize>,
+    ) -> Result<(), Contradiction> {
+        while let Some(idx) = queue.pop() {
+            match self.cells[idx] {
+                CellType::Target(n) => {
+                    let n = n as usize;
+                    let around = &self.neighbours[idx];
+                    let bulbs = around
+                        .iter()
+                        .filter(|&&v| state.marks[v] == Mark::Bulb)
+                        .count();
+                    let unknown = around
+                        .iter()
+                        .filter(|&&v| state.marks[v] == Mark::Unknown)
+                        .count();
+                    if bulbs > n || bulbs + unknown < n {
+                        return Err(Contradiction);
+                    }
+                    if unknown > 0 && bulbs == n {
+                        for &v in around {
+                            self.block(state, v, queue);
+                        }
+                    } else if unknown > 0 && bulbs + unknown == n {
+                        for &v in around {
+                            if state.marks[v] == Mark::Unknown {
+                                self.place_bulb(state, v, queue)?;
+                            }
+                        }
+                    }
+                }
+                CellType::Empty if !state.lit[idx] => {
+                    let forced = {
+                        let mut candidates = self.candidates(state, idx);
+                        match (candidates.next(), candidates.next()) {
+                            (None, _) => return Err(Contradiction),
+                            (Some(only), None) => Some(only),
+                            _ => None,
+                        }
+                    };
+                    if let Some(only) = forced {
+                        self.place_bulb(state, only, queue)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Cells that could still hold the bulb lighting `idx`.
+    pub(crate) fn candidates<'a>(
+        &'a self,
+        state: &'a State,
+        idx: usize,
+    ) -> impl Iterator<Item = usize> + 'a {
+        std::iter::once(idx)
+            .chain(self.sight[idx].iter().copied())
+            .filter(move |&v| state.marks[v] == Mark::Unknown)
+    }
+
+    // Try each open cell both ways; whichever choice fails forces the other.
+    fn probe(&self, state: &mut State) -> Result<(), Contradiction> {
+        let mut queue = Vec::new();
+        loop {
+            let mut changed = false;
+            for idx in 0..self.cells.len() {
+                if state.marks[idx] != Mark::Unknown {
+                    continue;
+                }
+
+                let mut trial = state.clone();
+                let bulb_fails = self.place_bulb(&mut trial, idx, &mut queue).is_err()
+                    || self.propagate(&mut trial, &mut queue).is_err();
+                queue.clear();
+                if bulb_fails {
+                    self.block(state, idx, &mut queue);
+                    self.propagate(state, &mut queue)?;
+                    changed = true;
+                    continue;
+                }
+
+                let mut trial = state.clone();
+                self.block(&mut trial, idx, &mut queue);
+                let block_fails = self.propagate(&mut trial, &mut queue).is_err();
+                queue.clear();
+                if block_fails {
+                    self.place_bulb(state, idx, &mut queue)?;
+                    self.propagate(state, &mut queue)?;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+    }
+
+    // Collect solutions into `found` until it holds `limit` of them.
+    fn search(
+        &self,
+        mut state: State,
+        mut queue: Vec<usize>,
+        limit: usize,
+        found: &mut Vec<State>,
+    ) {
+        if self.propagate(&mut state, &mut queue).is_err() || self.probe(&mut state).is_err() {
+            return;
+        }
+
+        // Branch on the unlit cell with the fewest ways to be lit.
+        let branch = (0..self.cells.len())
+            .filter(|&idx| !state.lit[idx])
+            .map(|idx| self.candidates(&state, idx).collect::<Vec<usize>>())
+            .min_by_key(|candidates| candidates.len());
+
+        let Some(candidates) = branch else {
+            // Everything is lit, so every cell is decided and the clues hold.
+            found.push(state);
+            return;
+        };
+
+        // The k-th branch lights the cell from candidate k and rules out the
+        // earlier ones, so no solution is reached twice.
+        for (k, &bulb) in candidates.iter().enumerate() {
+            let mut next = state.clone();
+            let mut queue = Vec::new();
+            for &other in &candidates[..k] {
+                self.block(&mut next, other, &mut queue);
+            }
+            if self.place_bulb(&mut next, bulb, &mut queue).is_err() {
+                continue;
+            }
+            self.search(next, queue, limit, found);
+            if found.len() >= limit {
+                return;
+            }
+        }
+    }
+
+    // Solutions reachable from `state`, which may already hold some decisions.
+    pub(crate) fn solutions_from(&self, state: State, limit: usize) -> Vec<State> {
+        let mut found = Vec::new();
+        if limit > 0 {
+            let queue = (0..self.cells.len()).collect();
+            self.search(state, queue, limit, &mut found);
+        }
+        found
+    }
+
+    fn solutions(&self, limit: usize) -> Vec<State> {
+        self.solutions_from(self.initial_state(), limit)
+    }
+
+    pub(crate) fn position(&self, idx: usize) -> (usize, usize) {
+        (idx / self.cols, idx % self.cols)
+    }
+
+    fn bulbs(&self, state: &State) -> Vec<Vec<bool>> {
+        state
+            .marks
+            .chunks(self.cols.max(1))
+            .map(|row| row.iter().map(|mark| *mark == Mark::Bulb).collect())
+            .collect()
+    }
+}
+
+/// How many solutions a board has, as far as a puzzle's well-formedness goes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Uniqueness {
+    NoSolution,
+    Unique,
+    Multiple,
+}
+
+/// Find a bulb placement that solves `board`, or `None` if the puzzle has no
+/// solution. Ragged boards, whose rows differ in length, never have one.
+pub fn solve(board: &[Vec<CellType>]) -> Option<Vec<Vec<bool>>> {
+    let grid = Grid::new(board)?;
+    grid.solutions(1).first().map(|solved| grid.bulbs(solved))
+}
+
+/// Every solution of `board`, up to `limit` of them, as bulb grids.
+pub fn find_solutions(board: &[Vec<CellType>], limit: usize) -> Vec<Vec<Vec<bool>>> {
+    let Some(grid) = Grid::new(board) else {
+        return Vec::new();
+    };
+    grid.solutions(limit)
+        .iter()
+        .map(|solved| grid.bulbs(solved))
+        .collect()
+}
+
+/// Count the solutions of `board`, stopping as soon as `limit` have been found.
+pub fn count_solutions(board: &[Vec<CellType>], limit: usize) -> usize {
+    Grid::new(board).map_or(0, |grid| grid.solutions(limit).len())
+}
+
+/// Classify `board` as having no, exactly one, or several solutions.
+pub fn uniqueness(board: &[Vec<CellType>]) -> Uniqueness {
+    match count_solutions(board, 2) {
+        0 => Uniqueness::NoSolution,
+        1 => Uniqueness::Unique,
+        _ => Uniqueness::Multiple,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rule_violations;
+    use crate::notation;
+
+    fn board(text: &str) -> Vec<Vec<CellType>> {
+        notation::parse(text).unwrap().puzzle.board()
+    }
+
+    #[test]
+    fn unique_board() {
+        let board = board("- - 2 -\n- x - -\n- - - 1");
+        assert_eq!(uniqueness(&board), Uniqueness::Unique);
+        let solution = solve(&board).unwrap();
+        assert_eq!(rule_violations(&board, &solution), Some(Vec::new()));
+    }
+
+    #[test]
+    fn board_with_several_solutions() {
+        let board = board("- - -\n- x -");
+        assert_eq!(uniqueness(&board), Uniqueness::Multiple);
+        let solutions = find_solutions(&board, 10);
+        assert!(solutions.len() > 1);
+        for solution in &solutions {
+            assert_eq!(rule_violations(&board, solution), Some(Vec::new()));
+        }
+    }
+
+    #[test]
+    fn board_without_solution() {
+        let board = board("1 x");
+        assert_eq!(uniqueness(&board), Uniqueness::NoSolution);
+        assert_eq!(solve(&board), None);
+    }
+
+    #[test]
+    fn counting_stops_at_the_limit() {
+        let board = board("- - - -\n- - - -");
+        assert_eq!(count_solutions(&board, 3), 3);
+    }
+
+    #[test]
+    fn ragged_board_has_no_solution() {
+        let board = vec![vec![CellType::Empty; 2], vec![CellType::Empty]];
+        assert_eq!(solve(&board), None);
+        assert!(find_solutions(&board, 2).is_empty());
+        assert_eq!(uniqueness(&board), Uniqueness::NoSolution);
+    }
+}
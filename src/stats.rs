@@ -0,0 +1,226 @@
+use akartui_rs::archive::{self, PuzzleStatus};
+use akartui_rs::game::PuzzleId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Completion records for every puzzle that has been played, kept in
+// `stats.json` in the data directory.
+
+fn stats_path() -> PathBuf {
+    archive::data_dir().join("stats.json")
+}
+
+const RECENT_LIMIT: usize = 50;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PuzzleRecord {
+    pub rows: usize,
+    pub cols: usize,
+    pub solved: bool,
+    /// Solved at least once without hints, checks or reveals.
+    #[serde(default)]
+    pub solved_clean: bool,
+    pub best_secs: Option<u64>,
+    pub attempts: u32,
+    pub undos: u32,
+    pub hints: u32,
+}
+
+/// One finished or interrupted play session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub puzzle_id: PuzzleId,
+    pub solved: bool,
+    pub secs: u64,
+    pub ended_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub puzzles: BTreeMap<PuzzleId, PuzzleRecord>,
+    /// Newest last.
+    pub recent: Vec<Session>,
+}
+
+/// Totals across every record.
+#[derive(Debug, Default)]
+pub struct Totals {
+    pub played: usize,
+    pub solved: usize,
+    pub attempts: u32,
+    pub undos: u32,
+    pub hints: u32,
+}
+
+impl Stats {
+    /// Load the stats file, starting fresh if there is none. A file that
+    /// cannot be read is moved to `stats.json.bak` before the next save can
+    /// overwrite it, and the error says so.
+    pub fn load() -> Result<Stats, String> {
+        Stats::load_from(&stats_path())
+    }
+
+    fn load_from(path: &Path) -> Result<Stats, String> {
+        let error = match fs::read_to_string(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Stats::default()),
+            Err(e) => e.to_string(),
+            Ok(text) => match serde_json::from_str(&text) {
+                Ok(stats) => return Ok(stats),
+                Err(e) => e.to_string(),
+            },
+        };
+        let backup = path.with_extension("json.bak");
+        Err(match fs::rename(path, &backup) {
+            Ok(()) => format!(
+                "Could not read stats ({}); kept them as {} and started afresh",
+                error,
+                backup.display()
+            ),
+            Err(_) => format!("Could not read {}: {}", path.display(), error),
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        self.save_to(&stats_path())
+    }
+
+    // Write beside the file and rename over it, so a crash mid-write cannot
+    // leave half a file behind.
+    fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        fs::rename(partial, path)
+    }
+
+    /// Count a fresh start on `puzzle_id`.
+    pub fn start_attempt(&mut self, puzzle_id: &PuzzleId, rows: usize, cols: usize) {
+        let record = self.puzzles.entry(puzzle_id.clone()).or_default();
+        record.rows = rows;
+        record.cols = cols;
+        record.attempts += 1;
+    }
+
+    /// Fold one play session into the puzzle's record. `elapsed` is the total
+    /// time on the attempt so far; `undos` and `hints` are this session's, and
+    /// `clean` says whether the attempt got by without any help.
+    pub fn end_session(
+        &mut self,
+        puzzle_id: &PuzzleId,
+        solved: bool,
+        clean: bool,
+        elapsed: Duration,
+        undos: u32,
+        hints: u32,
+    ) {
+        let secs = elapsed.as_secs();
+        let record = self.puzzles.entry(puzzle_id.clone()).or_default();
+        record.undos += undos;
+        record.hints += hints;
+        if solved {
+            record.solved = true;
+            record.solved_clean |= clean;
+            record.best_secs = Some(record.best_secs.map_or(secs, |best| best.min(secs)));
+        }
+
+        self.recent.push(Session {
+            puzzle_id: puzzle_id.clone(),
+            solved,
+            secs,
+            ended_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+        });
+        if self.recent.len() > RECENT_LIMIT {
+            self.recent.drain(..self.recent.len() - RECENT_LIMIT);
+        }
+    }
+
+    /// Status of `puzzle_id`, given whether it has an unfinished save.
+    pub fn status(&self, puzzle_id: &PuzzleId, saved: bool) -> PuzzleStatus {
+        match self.puzzles.get(puzzle_id) {
+            Some(record) if record.solved_clean => PuzzleStatus::Solved,
+            Some(record) if record.solved => PuzzleStatus::SolvedWithHints,
+            _ if saved => PuzzleStatus::InProgress,
+            _ => PuzzleStatus::Unsolved,
+        }
+    }
+
+    pub fn best_secs(&self, puzzle_id: &PuzzleId) -> Option<u64> {
+        self.puzzles.get(puzzle_id)?.best_secs
+    }
+
+    pub fn totals(&self) -> Totals {
+        let mut totals = Totals::default();
+        for record in self.puzzles.values() {
+            totals.played += 1;
+            totals.solved += usize::from(record.solved);
+            totals.attempts += record.attempts;
+            totals.undos += record.undos;
+            totals.hints += record.hints;
+        }
+        totals
+    }
+
+    /// Solved and played puzzle counts for each board size, smallest first.
+    pub fn by_size(&self) -> BTreeMap<(usize, usize), (usize, usize)> {
+        let mut sizes = BTreeMap::new();
+        for record in self.puzzles.values() {
+            let (solved, played) = sizes.entry((record.rows, record.cols)).or_insert((0, 0));
+            *solved += usize::from(record.solved);
+            *played += 1;
+        }
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory per test, as they run in parallel.
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("akartui-stats-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn round_trips_through_the_file() {
+        let dir = scratch("round-trip");
+        let path = dir.join("stats.json");
+        assert!(Stats::load_from(&path).unwrap().puzzles.is_empty());
+
+        let mut stats = Stats::default();
+        stats.start_attempt(&PuzzleId::Archive(3), 7, 7);
+        stats.save_to(&path).unwrap();
+        let loaded = Stats::load_from(&path).unwrap();
+        assert_eq!(loaded.puzzles[&PuzzleId::Archive(3)].attempts, 1);
+        assert!(!dir.join("stats.json.tmp").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_an_unreadable_file() {
+        let dir = scratch("unreadable");
+        let path = dir.join("stats.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "{ not json").unwrap();
+
+        let error = Stats::load_from(&path).unwrap_err();
+        assert!(error.contains("stats.json.bak"), "{}", error);
+        assert_eq!(
+            fs::read_to_string(dir.join("stats.json.bak")).unwrap(),
+            "{ not json"
+        );
+        assert!(!path.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
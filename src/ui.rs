@@ -4,12 +4,13 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListDirection, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListDirection, ListItem, Paragraph, Wrap},
     Frame,
 };
 
-use crate::app::{App, CurrentScreen};
-use crate::game::CellDisplay;
+use crate::app::{App, CurrentScreen, Popup};
+use akartui_rs::game::{CellDisplay, PlayerObject};
+use akartui_rs::logic::Difficulty;
 // use serde_json::Value;
 // use std::fs;
 // use std::path::Path;
@@ -41,15 +42,59 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
     match app.current_screen {
         CurrentScreen::Menu => draw_menu_content(frame, app, middle[1]),
         CurrentScreen::Archive => draw_archive_content(frame, app, middle[1]),
+        CurrentScreen::Generate => draw_generate_content(frame, app, middle[1]),
+        CurrentScreen::Import => draw_import_content(frame, app, middle[1]),
+        CurrentScreen::Statistics => draw_statistics_content(frame, app, middle[1]),
         CurrentScreen::Game => draw_game_content(frame, app, middle[1]),
+        CurrentScreen::Paused => draw_paused_content(frame, app, middle[1]),
         CurrentScreen::Settings => draw_settings_content(frame, app, middle[1]),
         CurrentScreen::Help => draw_help_content(frame, app, middle[1]),
         CurrentScreen::Exiting => draw_exiting_content(frame, app, middle[1]),
         CurrentScreen::Win => draw_win(frame, app, middle[1]),
     }
+
+    if let Some(popup) = &app.popup {
+        draw_popup(frame, popup, chunks[1]);
+    }
+}
+
+// A bordered message centered over `area`; errors get a red border.
+fn draw_popup(frame: &mut Frame, popup: &Popup, area: Rect) {
+    let text = popup.text.trim_end();
+    let width = (area.width * 3 / 4).max(20).min(area.width);
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let lines = text
+        .lines()
+        .map(|line| line.chars().count().div_ceil(inner).max(1));
+    let height = (lines.sum::<usize>() as u16 + 4).min(area.height);
+    let rect = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let border = if popup.is_error {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    };
+    let para = Paragraph::new(format!("{}\n\nPress any key to close", text))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border)
+                .title(popup.title.as_str()),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(Clear, rect);
+    frame.render_widget(para, rect);
 }
 
 // Header/info
+fn difficulty_label(difficulty: Option<Difficulty>) -> &'static str {
+    difficulty.map_or("Unsolvable", |difficulty| difficulty.label())
+}
+
 fn draw_info(frame: &mut Frame, app: &App, area: Rect) {
     let info_chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -60,13 +105,14 @@ fn draw_info(frame: &mut Frame, app: &App, area: Rect) {
         ])
         .split(area);
     let left_info = match app.current_screen {
-        CurrentScreen::Game => {
+        CurrentScreen::Game | CurrentScreen::Paused => {
             if let Some(game) = &app.game {
                 if let Some(puzzle) = &game.puzzle {
                     let meta = &puzzle.metadata;
                     format!(
-                        "Puzzle ID: {}\nType: {}\nAuthor: {}\nSize: {}x{}\nSource: {}\nInfo: {}",
+                        "Puzzle ID: {}\nDifficulty: {}\nType: {}\nAuthor: {}\nSize: {}x{}\nSource: {}\nInfo: {}",
                         puzzle.id,
+                        difficulty_label(game.difficulty()),
                         meta.puzzle_type,
                         meta.author,
                         meta.size.rows,
@@ -82,8 +128,8 @@ fn draw_info(frame: &mut Frame, app: &App, area: Rect) {
             }
         }
         CurrentScreen::Archive => {
-            if let Some(selected) = app.archive_list.selected() {
-                format!("Selected puzzle: {:03}", selected + 1)
+            if let Some(puzzle_id) = app.selected_archive_id() {
+                format!("Selected puzzle: {}", puzzle_id)
             } else {
                 "Select a puzzle".to_string()
             }
@@ -125,7 +171,9 @@ fn draw_info(frame: &mut Frame, app: &App, area: Rect) {
         app.timer_string(),
         match app.current_screen {
             CurrentScreen::Game => "Playing",
+            CurrentScreen::Paused => "Paused",
             CurrentScreen::Archive => "Browsing",
+            CurrentScreen::Generate => "Generating",
             CurrentScreen::Win => "Finished",
             _ => "",
         }
@@ -137,14 +185,48 @@ fn draw_info(frame: &mut Frame, app: &App, area: Rect) {
 
 // Footer/helper
 fn draw_helper(frame: &mut Frame, app: &App, area: Rect) {
+    if app.current_screen == CurrentScreen::Game {
+        if let Some(hint) = app.game.as_ref().and_then(|game| game.hint.as_ref()) {
+            let para = Paragraph::new(format!("{}: {}", hint.technique.label(), hint.reason))
+                .block(Block::default().borders(Borders::ALL).title("Hint"));
+            frame.render_widget(para, area);
+            return;
+        }
+    }
+    if let Some(notice) = &app.notice {
+        let para = Paragraph::new(notice.as_str())
+            .block(Block::default().borders(Borders::ALL).title("Helper"));
+        frame.render_widget(para, area);
+        return;
+    }
+    if app.current_screen == CurrentScreen::Game {
+        if let Some(wrong) = app.game.as_ref().and_then(|game| game.check.as_ref()) {
+            let text = if wrong.is_empty() {
+                "No mistakes so far.".to_string()
+            } else {
+                format!("{} wrong placement(s) marked in red.", wrong.len())
+            };
+            let para =
+                Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Check"));
+            frame.render_widget(para, area);
+            return;
+        }
+    }
     let text = match app.current_screen {
         CurrentScreen::Game => {
-            "<Arrow Keys>: Move  <Space>: Lightbulb  <F>: Flag  <U>: Undo <Q>: Back"
+            "<Arrow Keys>: Move  <Space>: Lightbulb  <F>: Flag  <U>: Undo  <Ctrl-R>: Redo  <H>: Hint  <C>: Check  <R>: Reveal cell  <S>: Solution  <W>: Save  <L>: Link  <T>: Text  <P>: Pause  <Q>: Back"
         }
+        CurrentScreen::Paused => "<Any key>: Resume  <Q>: Save and back",
         CurrentScreen::Archive => {
-            "<Arrow Keys>: Move  <Enter>: Start Game </[int]> filter puzzle  <Q>: Back"
+            "<Arrow Keys>: Move  <Enter>: Start/Continue  <N>: Start over  <L>: Link  <T>: Text  </query> search  <Q>: Back"
+        }
+        CurrentScreen::Generate if app.generation.is_some() => "<Esc>/<Q>: Cancel",
+        CurrentScreen::Generate => {
+            "<Up/Down>: Field  <Left/Right>: Adjust  <Enter>: Generate and play  <Q>: Back"
         }
+        CurrentScreen::Import => "<Enter>: Play  <Esc>: Back",
         CurrentScreen::Menu => "<Arrow Keys>: Menu  <Enter>: Select  <Q>: Quit",
+        CurrentScreen::Statistics => "<Q>: Back",
         CurrentScreen::Settings => "Settings Screen  <Q>: Back",
         CurrentScreen::Help => "<Q>: Back",
         CurrentScreen::Exiting => "<Enter>: Confirm Exit  <Q>: Cancel",
@@ -189,10 +271,12 @@ fn draw_archive_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
     // 2. 畫搜尋欄
     let input = if app.archive_input_mode {
         format!("Filter: {}", app.archive_input)
+    } else if let Some(ref error) = app.archive_error {
+        format!("Bad filter: {}", error)
     } else if let Some(ref filter) = app.archive_filter {
         format!("Current filter: /{}", filter)
     } else {
-        "Press / to filter by puzzle number".to_string()
+        "Press / to search, e.g. /author:Nikoli size:>=18 unsolved".to_string()
     };
 
     let para = Paragraph::new(input)
@@ -208,9 +292,29 @@ fn draw_archive_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
 }
 
 fn draw_menu_content(frame: &mut Frame, app: &mut App, area: Rect) {
-    let menu_items = vec![
+    let menu_items = [
         ("🟢 <G> New Game ", "Start a random puzzle", Color::Green),
+        (
+            "⏯ <C> Continue",
+            "Resume your last unfinished puzzle",
+            Color::LightYellow,
+        ),
+        (
+            "🎲 <N> Generate",
+            "Create a new puzzle with a unique solution",
+            Color::LightGreen,
+        ),
         ("A <A> Archive", "Browse all puzzles", Color::Cyan),
+        (
+            "🔗 <I> Import URL",
+            "Play a puzzle shared as a puzz.link URL",
+            Color::LightGreen,
+        ),
+        (
+            "📊 <T> Statistics",
+            "Your solves, best times and recent games",
+            Color::LightCyan,
+        ),
         (
             "⚙️ <S> Settings",
             "Configure your experience",
@@ -267,81 +371,73 @@ fn draw_menu_content(frame: &mut Frame, app: &mut App, area: Rect) {
 fn draw_archive_content(frame: &mut Frame, app: &mut App, area: Rect) {
     let mut lines = vec![];
 
-    if let Some(selected) = app.archive_list.selected() {
-        let puzzle_id = selected + 1;
-        lines.push(Line::from(vec![Span::styled(
-            format!("📄 Puzzle {:03}", puzzle_id),
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]));
-        lines.push(Line::from(""));
-
-        // 顯示 metadata
-        if let Some(game) = &app.game {
-            if let Some(puzzle) = &game.puzzle {
-                let meta = &puzzle.metadata;
-                lines.push(Line::from(vec![
-                    Span::styled("Type: ", Style::default().fg(Color::Cyan)),
-                    Span::raw(&meta.puzzle_type),
-                ]));
-                lines.push(Line::from(vec![
-                    Span::styled("Author: ", Style::default().fg(Color::Cyan)),
-                    Span::raw(&meta.author),
-                ]));
-                lines.push(Line::from(vec![
-                    Span::styled("Size: ", Style::default().fg(Color::Cyan)),
-                    Span::raw(format!("{} x {}", meta.size.rows, meta.size.cols)),
-                ]));
-                lines.push(Line::from(vec![
-                    Span::styled("Source: ", Style::default().fg(Color::Cyan)),
-                    Span::raw(&meta.source),
-                ]));
+    match app.selected_preview() {
+        Some(Ok(preview)) => {
+            let puzzle = &preview.puzzle;
+            lines.push(Line::from(vec![Span::styled(
+                format!("📄 Puzzle {}", puzzle.id),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )]));
+            lines.push(Line::from(""));
+
+            // 顯示 metadata
+            let meta = &puzzle.metadata;
+            let fields = [
+                ("Author: ", meta.author.clone()),
+                ("Size: ", format!("{} x {}", meta.size.rows, meta.size.cols)),
+                ("Clues: ", preview.clues.to_string()),
+                (
+                    "Difficulty: ",
+                    difficulty_label(preview.difficulty).to_string(),
+                ),
+                ("Source: ", meta.source.clone()),
+                ("Info: ", meta.info.clone()),
+            ];
+            for (label, value) in fields {
                 lines.push(Line::from(vec![
-                    Span::styled("Info: ", Style::default().fg(Color::Cyan)),
-                    Span::raw(&meta.info),
+                    Span::styled(label, Style::default().fg(Color::Cyan)),
+                    Span::raw(value),
                 ]));
+            }
 
-                lines.push(Line::from("")); // 空行
-                lines.push(Line::from(Span::styled(
-                    "🧩 Mini Board Preview",
-                    Style::default()
-                        .fg(Color::LightGreen)
-                        .add_modifier(Modifier::BOLD),
-                )));
-                // 印出棋盤
-                for row in &puzzle.problem {
-                    let row_str: String = row
-                        .iter()
-                        .map(|cell| match cell.as_str() {
-                            "x" => "█", // 牆
-                            "0" => "0",
-                            "1" => "1",
-                            "2" => "2",
-                            "3" => "3",
-                            "4" => "4",
-                            _ => "·", // 空格
-                        })
-                        .collect();
-                    lines.push(Line::from(row_str));
-                }
-            } else {
-                lines.push(Line::from(Span::styled(
-                    "No metadata loaded.",
-                    Style::default().fg(Color::Red),
-                )));
+            lines.push(Line::from("")); // 空行
+            lines.push(Line::from(Span::styled(
+                "🧩 Mini Board Preview",
+                Style::default()
+                    .fg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            // 印出棋盤
+            for row in &puzzle.problem {
+                let row_str: String = row
+                    .iter()
+                    .map(|cell| match cell.as_str() {
+                        "x" => "█", // 牆
+                        "0" => "0",
+                        "1" => "1",
+                        "2" => "2",
+                        "3" => "3",
+                        "4" => "4",
+                        _ => "·", // 空格
+                    })
+                    .collect();
+                lines.push(Line::from(row_str));
             }
-        } else {
+        }
+        Some(Err(e)) => {
             lines.push(Line::from(Span::styled(
-                "No puzzle loaded.",
+                format!("Failed to load puzzle: {}", e),
                 Style::default().fg(Color::Red),
             )));
         }
-    } else {
-        lines.push(Line::from(Span::styled(
-            "Please select a puzzle from the left.",
-            Style::default().fg(Color::Gray),
-        )));
+        None => {
+            lines.push(Line::from(Span::styled(
+                "Please select a puzzle from the left.",
+                Style::default().fg(Color::Gray),
+            )));
+        }
     }
 
     let para = Paragraph::new(lines)
@@ -358,6 +454,107 @@ fn draw_archive_content(frame: &mut Frame, app: &mut App, area: Rect) {
     frame.render_widget(para, area);
 }
 
+fn draw_generate_content(frame: &mut Frame, app: &mut App, area: Rect) {
+    let options = &app.generator_options;
+    let fields = [
+        ("Rows", options.rows.to_string()),
+        ("Cols", options.cols.to_string()),
+        ("Walls", format!("{}%", options.wall_percent)),
+    ];
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "🎲 Generate a Puzzle",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for (index, (name, value)) in fields.iter().enumerate() {
+        let style = if index == app.generator_field {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<6}", name), Style::default().fg(Color::Cyan)),
+            Span::styled(format!("< {} >", value), style),
+        ]));
+    }
+    lines.push(Line::from(""));
+    if let Some(generation) = &app.generation {
+        let progress = &generation.progress;
+        let (checked, clues) = progress.clues();
+        let stage = if clues == 0 {
+            format!(
+                "ruling out other answers, {} wall(s) added",
+                progress.walls_added()
+            )
+        } else {
+            format!("removing clues, {}/{} tried", checked, clues)
+        };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Generating for {}s: {}",
+                generation.started.elapsed().as_secs(),
+                stage
+            ),
+            Style::default().fg(Color::LightYellow),
+        )));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "Every generated puzzle has exactly one solution.",
+        Style::default()
+            .fg(Color::Gray)
+            .add_modifier(Modifier::ITALIC),
+    )));
+
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Generate"))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(para, area);
+}
+
+fn draw_import_content(frame: &mut Frame, app: &mut App, area: Rect) {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "🔗 Import a puzz.link URL",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("URL: ", Style::default().fg(Color::Cyan)),
+            Span::raw(format!("{}_", app.import_input)),
+        ]),
+        Line::from(""),
+    ];
+    if let Some(error) = &app.import_error {
+        lines.push(Line::from(Span::styled(
+            format!("Cannot import: {}", error),
+            Style::default().fg(Color::Red),
+        )));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "Paste a link like https://puzz.link/p?lightup/10/10/... and press Enter.",
+        Style::default()
+            .fg(Color::Gray)
+            .add_modifier(Modifier::ITALIC),
+    )));
+
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Import"))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(para, area);
+}
+
 // 右側內容：遊戲
 fn draw_game_content(frame: &mut Frame, app: &mut App, area: Rect) {
     if let Some(game) = &app.game {
@@ -374,27 +571,76 @@ fn draw_game_content(frame: &mut Frame, app: &mut App, area: Rect) {
         let pad_x = (area.width - board_width) / 2;
         let pad_y = (area.height - board_height) / 2;
 
-        for i in 0..rows {
-            for j in 0..cols {
+        for (i, display_row) in display.iter().enumerate() {
+            for (j, cell) in display_row.iter().enumerate() {
                 let x = area.x + pad_x + j as u16 * cell_width;
                 let y = area.y + pad_y + i as u16 * cell_height;
                 let cell_area = Rect::new(x, y, cell_width, cell_height);
 
-                let (title, style): (String, Style) = match display[i][j] {
+                let (title, style): (String, Style) = match *cell {
                     CellDisplay::Wall => ("██".to_string(), Style::default().fg(Color::DarkGray)),
                     CellDisplay::Target(n) => {
                         let color = if n == 0 { Color::Green } else { Color::White };
                         (format!("{}", n), Style::default().fg(color))
                     }
+                    CellDisplay::TargetOverfilled(n) => (
+                        format!("-{}", n),
+                        Style::default()
+                            .fg(Color::White)
+                            .bg(Color::Red)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    CellDisplay::TargetUnreachable(n) => (
+                        format!("{}", n),
+                        Style::default()
+                            .fg(Color::LightRed)
+                            .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+                    ),
                     CellDisplay::LightBulb => {
                         ("💡".to_string(), Style::default().fg(Color::LightYellow))
                     }
+                    CellDisplay::LightBulbConflict => (
+                        "💡".to_string(),
+                        Style::default().fg(Color::LightYellow).bg(Color::Red),
+                    ),
                     CellDisplay::Light(n) => (format!("{}", n), Style::default().fg(Color::Yellow)),
                     CellDisplay::Flag => ("P".to_string(), Style::default().fg(Color::Red)),
                     CellDisplay::Dark => ("".to_string(), Style::default().fg(Color::Black)),
                 };
 
+                let mut title = title;
                 let mut cell_style = style;
+                if let Some(solution) = game.show_solution.then(|| game.solution()).flatten() {
+                    let placed = matches!(
+                        cell,
+                        CellDisplay::LightBulb | CellDisplay::LightBulbConflict
+                    );
+                    if solution[i][j] && !placed {
+                        title = "o".to_string();
+                        cell_style = Style::default().fg(Color::Black).bg(Color::Green);
+                    } else if solution[i][j] {
+                        cell_style = cell_style.bg(Color::Green);
+                    } else if placed {
+                        cell_style = cell_style.bg(Color::Red);
+                    }
+                }
+                if let Some(hint) = &game.hint {
+                    if hint.cells.contains(&(i, j)) {
+                        let color = match hint.object {
+                            Some(PlayerObject::Lightbulb) => Color::Green,
+                            Some(_) => Color::Magenta,
+                            None => Color::Red,
+                        };
+                        cell_style = cell_style.bg(color);
+                    } else if hint.source == Some((i, j)) {
+                        cell_style = cell_style.bg(Color::Cyan);
+                    }
+                }
+                if let Some(wrong) = &game.check {
+                    if wrong.contains(&(i, j)) {
+                        cell_style = cell_style.bg(Color::Red);
+                    }
+                }
                 if (i, j) == game.cursor_position {
                     cell_style = cell_style.bg(Color::Blue);
                 }
@@ -411,6 +657,75 @@ fn draw_game_content(frame: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
+fn fmt_secs(secs: u64) -> String {
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+fn draw_statistics_content(frame: &mut Frame, app: &mut App, area: Rect) {
+    let stats = &app.stats;
+    let heading = |text: &'static str| {
+        Line::from(Span::styled(
+            text,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ))
+    };
+
+    let totals = stats.totals();
+    let mut lines = vec![
+        heading("📊 Totals"),
+        Line::from(format!(
+            "Puzzles played: {}   Solved: {}   Attempts: {}",
+            totals.played, totals.solved, totals.attempts
+        )),
+        Line::from(format!("Undos: {}   Hints: {}", totals.undos, totals.hints)),
+        Line::from(""),
+        heading("📐 Solve rate by size"),
+    ];
+    let sizes = stats.by_size();
+    if sizes.is_empty() {
+        lines.push(Line::from("No puzzles played yet."));
+    }
+    for ((rows, cols), (solved, played)) in sizes {
+        lines.push(Line::from(format!(
+            "{:>3} x {:<3}  {}/{} solved ({}%)",
+            rows,
+            cols,
+            solved,
+            played,
+            solved * 100 / played
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(heading("🕘 Recent games"));
+    if stats.recent.is_empty() {
+        lines.push(Line::from("No games finished yet."));
+    }
+    for session in stats.recent.iter().rev().take(10) {
+        let (status, color) = if session.solved {
+            ("Solved    ", Color::Green)
+        } else {
+            ("Unfinished", Color::Gray)
+        };
+        let best = stats
+            .puzzles
+            .get(&session.puzzle_id)
+            .and_then(|record| record.best_secs)
+            .map_or(String::new(), |best| format!("  (best {})", fmt_secs(best)));
+        lines.push(Line::from(vec![
+            Span::raw(format!("Puzzle {}  ", session.puzzle_id)),
+            Span::styled(status, Style::default().fg(color)),
+            Span::raw(format!("  {}{}", fmt_secs(session.secs), best)),
+        ]));
+    }
+
+    let para =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Statistics"));
+    frame.render_widget(para, area);
+}
+
 fn draw_settings_content(frame: &mut Frame, _app: &mut App, area: Rect) {
     let para = Paragraph::new("Settings screen (not implemented yet)")
         .block(Block::default().borders(Borders::ALL).title("Settings"));
@@ -504,8 +819,36 @@ fn draw_exiting_content(frame: &mut Frame, _app: &mut App, area: Rect) {
     frame.render_widget(para, area);
 }
 
-fn draw_win(frame: &mut Frame, _app: &mut App, area: Rect) {
-    let text = "Congratulations! You Win!\n\nPress Q to return to menu";
+// The board stays hidden while paused, so the clock cannot be cheated.
+fn draw_paused_content(frame: &mut Frame, app: &mut App, area: Rect) {
+    let text = format!(
+        "Paused\n\nTime: {}\n\nPress any key to resume\nPress Q to save and return to menu",
+        app.timer_string()
+    );
+    let para = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title("Paused"))
+        .alignment(ratatui::layout::Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(para, area);
+}
+
+fn draw_win(frame: &mut Frame, app: &mut App, area: Rect) {
+    let mut text = format!(
+        "Congratulations! You Win!\n\nTime: {}\n",
+        app.timer_string()
+    );
+    if let Some(game) = &app.game {
+        let assists = game.assists;
+        if assists.is_clean() {
+            text.push_str("Clean solve: no hints, checks or reveals.\n");
+        } else {
+            text.push_str(&format!(
+                "Assisted solve: {} hint(s), {} check(s), {} cell reveal(s), {} solution view(s).\n",
+                assists.hints, assists.checks, assists.cell_reveals, assists.solution_reveals
+            ));
+        }
+    }
+    text.push_str("\nPress Q to return to menu");
     let para = Paragraph::new(text)
         .block(Block::default().borders(Borders::ALL).title("You Win!"))
         .wrap(Wrap { trim: true });